@@ -0,0 +1,49 @@
+//! Runs the game headless with ever larger crowds and prints how long a tick,
+//! the enemy grid's rebuild and its queries took at each size.
+//!
+//! ```cargo bench --bench stress```
+
+use std::process::Command;
+
+const CROWDS: [u32; 6] = [125, 250, 500, 1000, 2000, 4000];
+const TICKS: &str = "300";
+
+fn main() {
+    for crowd in CROWDS {
+        let output = Command::new(env!("CARGO_BIN_EXE_bevy-jam-3"))
+            .args(["--headless", "--seed", "1", "--ticks", TICKS])
+            .args(["--stress", &crowd.to_string()])
+            .output()
+            .expect("the game should start");
+        assert!(
+            output.status.success(),
+            "the game failed with {} extra enemies:\n{}",
+            crowd,
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        println!("--stress {crowd}");
+        let log = String::from_utf8_lossy(&output.stdout);
+        for line in log.lines().map(strip_colours) {
+            if let Some((_, message)) = line.split_once("headless: ") {
+                if message.contains(" ticks: mean") || message.starts_with("enemy grid") {
+                    println!("  {message}");
+                }
+            }
+        }
+    }
+}
+
+/// Drops the terminal colour codes the log is written with.
+fn strip_colours(line: &str) -> String {
+    let mut plain = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
@@ -1,4 +1,5 @@
-use crate::constants::{HOVERED_BUTTON, NORMAL_BUTTON, PRESSED_BUTTON};
+use crate::constants::PRESSED_BUTTON;
+use crate::gameplay::{StageCleared, Stages};
 use crate::states::AppState;
 use bevy::prelude::*;
 
@@ -6,6 +7,8 @@ pub struct CutscenePlugin;
 
 impl Plugin for CutscenePlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<CutsceneScript>();
+        app.add_system(queue_boss_cutscenes.in_set(OnUpdate(AppState::InGame)));
         app.add_system(create_menu.in_schedule(OnEnter(AppState::Cutscene)));
         app.add_system(spawn_background.in_schedule(OnEnter(AppState::Cutscene)));
         app.add_system(process_menu.in_set(OnUpdate(AppState::Cutscene)));
@@ -15,6 +18,16 @@ impl Plugin for CutscenePlugin {
     }
 }
 
+/// Which dialogue the next cutscene plays. Goes back to the intro after each
+/// cutscene.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CutsceneScript {
+    #[default]
+    Intro,
+    BossIntro,
+    BossDefeated,
+}
+
 #[derive(Resource)]
 struct CutsceneEntities {
     skip: Entity,
@@ -41,10 +54,102 @@ struct CutsceneInfo {
     doc_text: String,
     wife_text: String,
     baby_text: String,
+    #[allow(dead_code)]
     wife_big_boob: bool,
 }
 
-fn create_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+impl CutsceneScript {
+    fn scenes(self) -> Vec<CutsceneInfo> {
+        match self {
+            CutsceneScript::Intro => vec![
+                CutsceneInfo {
+                    doc_text: "So you understand the possible \n side effects right?".to_string(),
+                    wife_text: "".to_string(),
+                    baby_text: "".to_string(),
+                    wife_big_boob: false,
+                },
+                CutsceneInfo {
+                    doc_text: "".to_string(),
+                    wife_text: "Yeah yeah. \n Just give me the pill already!".to_string(),
+                    baby_text: "".to_string(),
+                    wife_big_boob: false,
+                },
+                CutsceneInfo {
+                    doc_text: "Here you go....".to_string(),
+                    wife_text: "".to_string(),
+                    baby_text: "".to_string(),
+                    wife_big_boob: false,
+                },
+                CutsceneInfo {
+                    doc_text: "".to_string(),
+                    wife_text: "*gulp*".to_string(),
+                    baby_text: "".to_string(),
+                    wife_big_boob: false,
+                },
+                CutsceneInfo {
+                    doc_text: "".to_string(),
+                    wife_text: "Oh my gosh! This is amazing! \n But something isn't right...."
+                        .to_string(),
+                    baby_text: "".to_string(),
+                    wife_big_boob: true,
+                },
+                CutsceneInfo {
+                    doc_text: "".to_string(),
+                    wife_text: "".to_string(),
+                    baby_text: "*RAWWWWRRRR*".to_string(),
+                    wife_big_boob: true,
+                },
+            ],
+            CutsceneScript::BossIntro => vec![
+                CutsceneInfo {
+                    doc_text: "Something big is coming. \n Much bigger than the others."
+                        .to_string(),
+                    wife_text: "".to_string(),
+                    baby_text: "".to_string(),
+                    wife_big_boob: true,
+                },
+                CutsceneInfo {
+                    doc_text: "".to_string(),
+                    wife_text: "That's... that's my firstborn?!".to_string(),
+                    baby_text: "".to_string(),
+                    wife_big_boob: true,
+                },
+                CutsceneInfo {
+                    doc_text: "".to_string(),
+                    wife_text: "".to_string(),
+                    baby_text: "*GOO GOO RAWWWWRRRR*".to_string(),
+                    wife_big_boob: true,
+                },
+            ],
+            CutsceneScript::BossDefeated => vec![
+                CutsceneInfo {
+                    doc_text: "It's down! \n I think that was the worst of it.".to_string(),
+                    wife_text: "".to_string(),
+                    baby_text: "".to_string(),
+                    wife_big_boob: true,
+                },
+                CutsceneInfo {
+                    doc_text: "".to_string(),
+                    wife_text: "There are more of them, \n aren't there?".to_string(),
+                    baby_text: "".to_string(),
+                    wife_big_boob: true,
+                },
+                CutsceneInfo {
+                    doc_text: "".to_string(),
+                    wife_text: "".to_string(),
+                    baby_text: "*rawr*".to_string(),
+                    wife_big_boob: true,
+                },
+            ],
+        }
+    }
+}
+
+fn create_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    script: Res<CutsceneScript>,
+) {
     let skip = commands
         .spawn(NodeBundle {
             style: Style {
@@ -95,44 +200,7 @@ fn create_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
         })
         .id();
 
-    let scenes = vec![
-        CutsceneInfo {
-            doc_text: "So you understand the possible \n side effects right?".to_string(),
-            wife_text: "".to_string(),
-            baby_text: "".to_string(),
-            wife_big_boob: false,
-        },
-        CutsceneInfo {
-            doc_text: "".to_string(),
-            wife_text: "Yeah yeah. \n Just give me the pill already!".to_string(),
-            baby_text: "".to_string(),
-            wife_big_boob: false,
-        },
-        CutsceneInfo {
-            doc_text: "Here you go....".to_string(),
-            wife_text: "".to_string(),
-            baby_text: "".to_string(),
-            wife_big_boob: false,
-        },
-        CutsceneInfo {
-            doc_text: "".to_string(),
-            wife_text: "*gulp*".to_string(),
-            baby_text: "".to_string(),
-            wife_big_boob: false,
-        },
-        CutsceneInfo {
-            doc_text: "".to_string(),
-            wife_text: "Oh my gosh! This is amazing! \n But something isn't right....".to_string(),
-            baby_text: "".to_string(),
-            wife_big_boob: true,
-        },
-        CutsceneInfo {
-            doc_text: "".to_string(),
-            wife_text: "".to_string(),
-            baby_text: "*RAWWWWRRRR*".to_string(),
-            wife_big_boob: true,
-        },
-    ];
+    let scenes = script.scenes();
 
     spawn_scene(&mut commands, &scenes[0], asset_server, skip);
 
@@ -164,7 +232,7 @@ fn spawn_scene(
                     translation: Vec3 {
                         x: -240.0,
                         y: -120.0,
-                        z: 2.0,
+                        z: 11.0,
                     },
                     scale: Vec3::new(6.0, 6.0, 0.0),
                     ..default()
@@ -189,7 +257,7 @@ fn spawn_scene(
                 .with_children(|parent| {
                     parent
                         .spawn(TextBundle::from_section(
-                            format!("{}", cutscene_info.doc_text),
+                            cutscene_info.doc_text.clone(),
                             TextStyle {
                                 font: asset_server.load("fonts/pixelsplitter.ttf"),
                                 font_size: 28.0,
@@ -210,7 +278,7 @@ fn spawn_scene(
                     translation: Vec3 {
                         x: 240.0,
                         y: -120.0,
-                        z: 2.0,
+                        z: 11.0,
                     },
                     scale: Vec3::new(6.0, 6.0, 0.0),
                     ..default()
@@ -235,7 +303,7 @@ fn spawn_scene(
                 .with_children(|parent| {
                     parent
                         .spawn(TextBundle::from_section(
-                            format!("{}", cutscene_info.wife_text),
+                            cutscene_info.wife_text.clone(),
                             TextStyle {
                                 font: asset_server.load("fonts/pixelsplitter.ttf"),
                                 font_size: 28.0,
@@ -256,7 +324,7 @@ fn spawn_scene(
                     translation: Vec3 {
                         x: -240.0,
                         y: -120.0,
-                        z: 2.0,
+                        z: 11.0,
                     },
                     scale: Vec3::new(6.0, 6.0, 0.0),
                     ..default()
@@ -281,7 +349,7 @@ fn spawn_scene(
                 .with_children(|parent| {
                     parent
                         .spawn(TextBundle::from_section(
-                            format!("{}", cutscene_info.baby_text),
+                            cutscene_info.baby_text.clone(),
                             TextStyle {
                                 font: asset_server.load("fonts/pixelsplitter.ttf"),
                                 font_size: 28.0,
@@ -294,6 +362,7 @@ fn spawn_scene(
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn process_menu(
     mut next_state: ResMut<NextState<AppState>>,
     mut interaction_query: Query<(&Interaction, &Children), (Changed<Interaction>, With<Button>)>,
@@ -304,7 +373,7 @@ fn process_menu(
             Interaction::Clicked => {
                 for &child in children.iter() {
                     if let Ok(mut text) = text_query.get_mut(child) {
-                        text.sections[0].style.color = PRESSED_BUTTON.into();
+                        text.sections[0].style.color = PRESSED_BUTTON;
                     }
                 }
                 next_state.set(AppState::InGame);
@@ -371,6 +440,7 @@ fn update_cutscene(
 
 fn destroy_menu(
     mut commands: Commands,
+    mut script: ResMut<CutsceneScript>,
     menu_data: Res<CutsceneEntities>,
     active_query: Query<(&ActiveCutscene, Entity)>,
     background_query: Query<(&CutsceneBackground, Entity)>,
@@ -385,10 +455,30 @@ fn destroy_menu(
 
     commands.entity(menu_data.skip).despawn_recursive();
     commands.entity(menu_data.cutscene).despawn_recursive();
+    *script = CutsceneScript::Intro;
+}
+
+/// Cuts away when a boss stage is up next and when a boss stage is cleared.
+fn queue_boss_cutscenes(
+    mut stage_cleared_events: EventReader<StageCleared>,
+    stages: Res<Stages>,
+    mut script: ResMut<CutsceneScript>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for cleared in stage_cleared_events.iter() {
+        if stages.is_boss_stage(cleared.stage) {
+            *script = CutsceneScript::BossDefeated;
+        } else if stages.is_boss_stage(cleared.stage + 1) {
+            *script = CutsceneScript::BossIntro;
+        } else {
+            continue;
+        }
+        next_state.set(AppState::Cutscene);
+    }
 }
 
 fn spawn_background(mut commands: Commands, map_query: Query<&CutsceneBackground>) {
-    for _ in map_query.iter() {
+    if !map_query.is_empty() {
         return;
     }
 
@@ -402,5 +492,6 @@ fn spawn_background(mut commands: Commands, map_query: Query<&CutsceneBackground
             ..default()
         })
         .insert(CutsceneBackground)
-        .insert(TransformBundle::from(Transform::from_xyz(0.0, 0.0, 1.0)));
+        // Above the arena, which is still there during boss cutscenes.
+        .insert(TransformBundle::from(Transform::from_xyz(0.0, 0.0, 10.0)));
 }
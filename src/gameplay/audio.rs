@@ -0,0 +1,72 @@
+use crate::{loading::AudioHandles, states::AppState};
+use bevy::prelude::*;
+
+use super::events::{DamageDealt, HitCause, PickupCollected, PlayerHit, SpitShotDown};
+use super::pickups::PickupKind;
+use super::player::{PlaySound, PlayerSoundType};
+
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(play_sounds.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(play_hit_sounds.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(play_pickup_sounds.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+/// Pellets landing, globs splashing and the player getting bitten have
+/// sounds of their own.
+fn play_hit_sounds(
+    mut damage_events: EventReader<DamageDealt>,
+    mut player_hit_events: EventReader<PlayerHit>,
+    mut spit_shot_down_events: EventReader<SpitShotDown>,
+    audio_handles: Res<AudioHandles>,
+    audio: Res<Audio>,
+) {
+    for _ in damage_events.iter() {
+        audio.play(Handle::weak(audio_handles.hit.id()));
+    }
+    for hit in player_hit_events.iter() {
+        let handle = match hit.cause {
+            HitCause::Bite => &audio_handles.bite,
+            HitCause::Spit => &audio_handles.hit,
+        };
+        audio.play(Handle::weak(handle.id()));
+    }
+    for _ in spit_shot_down_events.iter() {
+        audio.play(Handle::weak(audio_handles.hit.id()));
+    }
+}
+
+fn play_pickup_sounds(
+    mut pickup_events: EventReader<PickupCollected>,
+    audio_handles: Res<AudioHandles>,
+    audio: Res<Audio>,
+) {
+    for pickup in pickup_events.iter() {
+        let handle = match pickup.kind {
+            PickupKind::Ammo { .. } => &audio_handles.shell,
+            PickupKind::Heal { .. } => &audio_handles.cock,
+        };
+        audio.play(Handle::weak(handle.id()));
+    }
+}
+
+fn play_sounds(
+    mut play_sound_events: EventReader<PlaySound>,
+    audio_handles: Res<AudioHandles>,
+    audio: Res<Audio>,
+) {
+    for PlaySound(sound_type) in play_sound_events.iter() {
+        let handle = match sound_type {
+            PlayerSoundType::Cock => &audio_handles.cock,
+            PlayerSoundType::Gunshot => &audio_handles.gunshot,
+            PlayerSoundType::Shell => &audio_handles.shell,
+            PlayerSoundType::Bite => &audio_handles.bite,
+            PlayerSoundType::Empty => &audio_handles.empty,
+            PlayerSoundType::Hit => &audio_handles.hit,
+        };
+        audio.play(Handle::weak(handle.id()));
+    }
+}
@@ -0,0 +1,208 @@
+use crate::states::AppState;
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use bevy_rapier2d::prelude::*;
+use bevy_turborand::prelude::*;
+
+use super::boss::Boss;
+use super::clock::GameClock;
+use super::enemies::{Biting, Enemy, ARENA_HALF_SIZE};
+use super::player::Player;
+use super::spatial::{rebuild_enemy_grid, EnemyGrid};
+use super::{FixedSet, RngSet};
+
+pub struct AwarenessPlugin;
+
+impl Plugin for AwarenessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            update_enemy_states
+                .after(rebuild_enemy_grid)
+                .in_set(RngSet::EnemyStates)
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .in_base_set(FixedSet::Update)
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// Enemies further away than this can't see the player, cover or not.
+const SIGHT_DISTANCE: f32 = 320.0;
+/// Enemies this close notice the player even through cover.
+const HEARING_DISTANCE: f32 = 90.0;
+/// Wandering enemies this close to one that notices the player notice too.
+const SHOUT_RADIUS: f32 = 100.0;
+/// Pause between noticing the player and giving chase.
+const ALERT_DELAY: Duration = Duration::from_millis(400);
+/// How long a chasing enemy keeps after a player it can't see.
+const FORGET_AFTER: Duration = Duration::from_secs(5);
+/// Longest an enemy heads for one wander target.
+const WANDER_TIME: Duration = Duration::from_secs(3);
+/// Close enough to a wander target to pick the next one.
+const WANDER_REACHED: f32 = 10.0;
+/// Wind-up between touching the player and the bite landing.
+pub const BITE_WINDUP: Duration = Duration::from_millis(250);
+/// How long a biter reels from the knockback after a bite.
+pub const BITE_STUN: Duration = Duration::from_millis(500);
+
+/// What an enemy is doing. Times are game times.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EnemyState {
+    /// Hasn't noticed the player, and ambles toward `target` until `until`.
+    Wandering {
+        target: Vec2,
+        until: Duration,
+    },
+    /// Has just noticed the player, and gives chase at `until`.
+    Alerted {
+        until: Duration,
+    },
+    /// Heads for the player. Gives up `FORGET_AFTER` the player was last in
+    /// sight, unless it is a boss.
+    Chasing {
+        in_sight: bool,
+        last_seen: Duration,
+    },
+    /// Touching the player, with the bite landing at `bites_at`.
+    Attacking {
+        bites_at: Duration,
+    },
+    /// Reeling after a bite until `until`.
+    Stunned {
+        until: Duration,
+    },
+    Dead,
+}
+
+impl EnemyState {
+    /// Just appeared and hasn't noticed anything yet.
+    pub fn arriving(position: Vec2) -> Self {
+        Self::Wandering {
+            target: position,
+            until: Duration::ZERO,
+        }
+    }
+
+    /// Already after the player.
+    pub fn chasing(now: Duration) -> Self {
+        Self::Chasing {
+            in_sight: false,
+            last_seen: now,
+        }
+    }
+}
+
+/// Whether nothing fixed stands between `from` and `to`.
+fn line_of_sight(rapier_context: &RapierContext, from: Vec2, to: Vec2) -> bool {
+    let offset = to - from;
+    let distance = offset.length();
+    if distance <= f32::EPSILON {
+        return true;
+    }
+    let filter = QueryFilter::only_fixed().exclude_sensors();
+    rapier_context
+        .cast_ray(from, offset / distance, distance, true, filter)
+        .is_none()
+}
+
+fn wander_target(rng: &mut GlobalRng) -> Vec2 {
+    Vec2::new(rng.f32_normalized(), rng.f32_normalized()) * ARENA_HALF_SIZE
+}
+
+/// Moves every enemy's state along: noticing the player by sight or by being
+/// close, chasing, winding up bites on contact and recovering from them.
+pub fn update_enemy_states(
+    mut enemy_query: Query<(&mut Enemy, &Transform, Option<&Biting>, Option<&Boss>)>,
+    player_query: Query<&Transform, With<Player>>,
+    rapier_context: Res<RapierContext>,
+    clock: Res<GameClock>,
+    grid: Res<EnemyGrid>,
+    mut rng: ResMut<GlobalRng>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player = player_transform.translation.truncate();
+    let now = clock.elapsed();
+    let mut shouts = Vec::new();
+
+    for (mut enemy, transform, biting, boss) in enemy_query.iter_mut() {
+        let position = transform.translation.truncate();
+        let distance = position.distance(player);
+        let touching = biting.is_some();
+        let sees =
+            || distance <= SIGHT_DISTANCE && line_of_sight(&rapier_context, position, player);
+
+        enemy.state = match enemy.state {
+            EnemyState::Dead => continue,
+            EnemyState::Wandering { target, until } => {
+                if touching || distance <= HEARING_DISTANCE || sees() {
+                    shouts.push(position);
+                    EnemyState::Alerted {
+                        until: now + ALERT_DELAY,
+                    }
+                } else if now >= until || position.distance(target) <= WANDER_REACHED {
+                    EnemyState::Wandering {
+                        target: wander_target(&mut rng),
+                        until: now + WANDER_TIME,
+                    }
+                } else {
+                    continue;
+                }
+            }
+            EnemyState::Alerted { until } if now >= until => EnemyState::Chasing {
+                in_sight: sees(),
+                last_seen: now,
+            },
+            EnemyState::Alerted { .. } => continue,
+            EnemyState::Chasing { .. } if touching => EnemyState::Attacking {
+                bites_at: now + BITE_WINDUP,
+            },
+            EnemyState::Chasing { last_seen, .. } => {
+                if sees() {
+                    EnemyState::Chasing {
+                        in_sight: true,
+                        last_seen: now,
+                    }
+                } else if boss.is_none() && now >= last_seen + FORGET_AFTER {
+                    debug!("an enemy loses track of the player");
+                    EnemyState::Wandering {
+                        target: wander_target(&mut rng),
+                        until: now + WANDER_TIME,
+                    }
+                } else {
+                    EnemyState::Chasing {
+                        in_sight: false,
+                        last_seen,
+                    }
+                }
+            }
+            // Backing off before the bite lands makes it miss.
+            EnemyState::Attacking { .. } if !touching => EnemyState::chasing(now),
+            EnemyState::Attacking { .. } => continue,
+            EnemyState::Stunned { until } if now >= until => {
+                if touching {
+                    EnemyState::Attacking {
+                        bites_at: now + BITE_WINDUP,
+                    }
+                } else {
+                    EnemyState::chasing(now)
+                }
+            }
+            EnemyState::Stunned { .. } => continue,
+        };
+    }
+
+    // Noticing the player rouses the wanderers around it.
+    for shout in shouts {
+        for neighbour in grid.within(shout, SHOUT_RADIUS) {
+            if let Ok((mut enemy, ..)) = enemy_query.get_mut(neighbour.entity) {
+                if matches!(enemy.state, EnemyState::Wandering { .. }) {
+                    enemy.state = EnemyState::Alerted {
+                        until: now + ALERT_DELAY,
+                    };
+                }
+            }
+        }
+    }
+}
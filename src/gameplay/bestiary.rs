@@ -0,0 +1,267 @@
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy_turborand::prelude::*;
+use serde::Deserialize;
+
+use super::data::DataAsset;
+
+/// Every kind of enemy a stage can spawn.
+#[derive(Resource, Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "c4d2e8a1-5b7f-4f3e-9a06-1d8b3e6f2c59"]
+pub struct Bestiary {
+    pub archetypes: Vec<Archetype>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Archetype {
+    pub name: String,
+    pub health: i32,
+    /// Force pushing the enemy along, which with its mass sets its speed.
+    pub speed: f32,
+    pub radius: f32,
+    pub density: f32,
+    /// Health a bite takes off the player.
+    pub bite_damage: i32,
+    /// Added to the score when killed.
+    pub score: i32,
+    pub ai: EnemyAi,
+    #[serde(default)]
+    pub steering: Steering,
+    pub sprite: EnemySprite,
+    /// Enemies spawned where this one dies.
+    #[serde(default)]
+    pub split: Option<Split>,
+    /// Makes it a boss fighting in these phases, in order.
+    #[serde(default)]
+    pub boss_phases: Vec<BossPhase>,
+    /// First stage it shows up in.
+    #[serde(default = "first_stage")]
+    pub first_stage: u32,
+    /// Chance of being picked for a spawn, over the total weight of every
+    /// archetype in the stage. 0 never spawns on its own, only from a split.
+    pub weight: u32,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum EnemyAi {
+    /// Runs straight at the player.
+    Chase,
+    /// Closes in to `range` and backs off when the player gets nearer,
+    /// spitting at it from there if it has a `spit` attack.
+    Ranged {
+        range: f32,
+        #[serde(default)]
+        spit: Option<SpitAttack>,
+    },
+}
+
+/// Globs spat at the player, which pellets can shoot down.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct SpitAttack {
+    pub every_secs: f32,
+    /// Velocity the glob flies at.
+    pub speed: f32,
+    /// Health a glob takes off the player.
+    pub damage: i32,
+    pub radius: f32,
+}
+
+/// How much each steering behaviour adds to the pull toward the player,
+/// which has a weight of 1.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Steering {
+    /// Pushes away from enemies closer than `neighbour_radius`.
+    pub separation: f32,
+    /// Pulls toward the middle of nearby enemies of the same archetype.
+    pub cohesion: f32,
+    /// Pulls toward a flank around the player, so a crowd closes in from
+    /// all sides.
+    pub surround: f32,
+    /// How far away other enemies count as neighbours.
+    pub neighbour_radius: f32,
+}
+
+impl Default for Steering {
+    fn default() -> Self {
+        Self {
+            separation: 1.0,
+            cohesion: 0.0,
+            surround: 0.0,
+            neighbour_radius: 40.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EnemySprite {
+    /// Relative to the assets folder.
+    pub sheet: String,
+    /// Size of one frame in the sheet.
+    pub frame: (f32, f32),
+    pub columns: usize,
+    pub rows: usize,
+    /// Frames of the run animation, first to last.
+    pub run: (usize, usize),
+    /// Drawn size, in world units.
+    pub size: f32,
+    #[serde(default = "no_tint")]
+    pub tint: (f32, f32, f32),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Split {
+    /// Name of the archetype spawned.
+    pub into: String,
+    pub count: u32,
+}
+
+/// Lasts until the boss's health drops to `until_health` of its maximum.
+#[derive(Deserialize, Clone, Debug)]
+pub struct BossPhase {
+    pub until_health: f32,
+    /// Multiplies the archetype's speed.
+    #[serde(default = "full_speed")]
+    pub speed: f32,
+    #[serde(default)]
+    pub charge: Option<Charge>,
+    #[serde(default)]
+    pub summon: Option<Summon>,
+}
+
+/// A sudden lunge at the player.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Charge {
+    pub every_secs: f32,
+    /// Velocity the lunge starts at.
+    pub speed: f32,
+}
+
+/// Calls in other enemies around the boss.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Summon {
+    pub every_secs: f32,
+    pub archetype: String,
+    pub count: u32,
+}
+
+fn full_speed() -> f32 {
+    1.0
+}
+
+fn first_stage() -> u32 {
+    1
+}
+
+fn no_tint() -> (f32, f32, f32) {
+    (1.0, 1.0, 1.0)
+}
+
+impl EnemySprite {
+    pub fn tint(&self) -> Color {
+        Color::rgb(self.tint.0, self.tint.1, self.tint.2)
+    }
+}
+
+impl Archetype {
+    pub fn is_boss(&self) -> bool {
+        !self.boss_phases.is_empty()
+    }
+
+    /// The boss phase for health at `fraction` of the maximum.
+    pub fn boss_phase(&self, fraction: f32) -> usize {
+        self.boss_phases
+            .iter()
+            .position(|phase| fraction > phase.until_health)
+            .unwrap_or(self.boss_phases.len().saturating_sub(1))
+    }
+}
+
+impl Bestiary {
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.archetypes
+            .iter()
+            .position(|archetype| archetype.name == name)
+    }
+
+    /// Picks one of the archetypes spawning in `stage` by weight. Falls back
+    /// to the first archetype if none do.
+    pub fn pick(&self, stage: u32, rng: &mut GlobalRng) -> usize {
+        let in_stage = |archetype: &&Archetype| archetype.first_stage <= stage;
+        let total: u32 = self
+            .archetypes
+            .iter()
+            .filter(in_stage)
+            .map(|archetype| archetype.weight)
+            .sum();
+        if total == 0 {
+            return 0;
+        }
+
+        let mut roll = rng.u32(0..total);
+        for (index, archetype) in self.archetypes.iter().enumerate() {
+            if archetype.first_stage > stage {
+                continue;
+            }
+            if roll < archetype.weight {
+                return index;
+            }
+            roll -= archetype.weight;
+        }
+        0
+    }
+}
+
+impl DataAsset for Bestiary {
+    const PATH: &'static str = "enemies/bestiary.enemies.ron";
+
+    fn validate(&self) -> Result<(), String> {
+        if self.archetypes.is_empty() {
+            return Err("a bestiary needs at least one archetype".to_string());
+        }
+        for archetype in &self.archetypes {
+            if archetype.radius <= 0.0 {
+                return Err(format!("{} needs a radius above 0", archetype.name));
+            }
+            if let EnemyAi::Ranged {
+                spit: Some(spit), ..
+            } = archetype.ai
+            {
+                if spit.radius <= 0.0 || spit.speed <= 0.0 {
+                    return Err(format!(
+                        "{} needs a spit radius and speed above 0",
+                        archetype.name
+                    ));
+                }
+            }
+            let summons = archetype
+                .boss_phases
+                .iter()
+                .filter_map(|phase| phase.summon.as_ref());
+            for summon in summons {
+                if self.index_of(&summon.archetype).is_none() {
+                    return Err(format!(
+                        "{} summons unknown archetype {}",
+                        archetype.name, summon.archetype
+                    ));
+                }
+            }
+            if let Some(split) = &archetype.split {
+                match self.index_of(&split.into) {
+                    None => {
+                        return Err(format!(
+                            "{} splits into unknown archetype {}",
+                            archetype.name, split.into
+                        ));
+                    }
+                    // Keeps a kill from splitting forever.
+                    Some(index) if self.archetypes[index].split.is_some() => {
+                        return Err(format!("{} splits into a splitter", archetype.name));
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use crate::states::AppState;
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use bevy_rapier2d::prelude::*;
+use bevy_turborand::prelude::*;
+
+use super::bestiary::Bestiary;
+use super::clock::GameClock;
+use super::enemies::{spawn_telegraph, Enemy, ARENA_HALF_SIZE};
+use super::player::Player;
+use super::stages::Stages;
+use super::{FixedSet, RngSet};
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            run_boss_phases
+                .in_set(RngSet::Boss)
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .in_base_set(FixedSet::Update)
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// On enemies whose archetype has boss phases.
+#[derive(Component)]
+pub struct Boss {
+    pub max_health: i32,
+    /// Index into the archetype's `boss_phases`.
+    pub phase: usize,
+    /// Game time of the next charge and summon, once the phase has them.
+    next_charge: Option<Duration>,
+    next_summon: Option<Duration>,
+}
+
+impl Boss {
+    pub fn new(max_health: i32) -> Self {
+        Self {
+            max_health,
+            phase: 0,
+            next_charge: None,
+            next_summon: None,
+        }
+    }
+
+    /// Health left, from 0 to 1.
+    pub fn health_fraction(&self, enemy: &Enemy) -> f32 {
+        (enemy.health().max(0) as f32 / self.max_health.max(1) as f32).min(1.0)
+    }
+}
+
+/// Moves bosses on to the phase for their health, then charges and summons
+/// as the phase says. Each attack waits a full interval after a phase starts.
+fn run_boss_phases(
+    mut commands: Commands,
+    mut boss_query: Query<(&Enemy, &mut Boss, &Transform, Entity)>,
+    player_query: Query<&Transform, With<Player>>,
+    clock: Res<GameClock>,
+    bestiary: Res<Bestiary>,
+    stages: Res<Stages>,
+    mut rng: ResMut<GlobalRng>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let now = clock.elapsed();
+
+    for (enemy, mut boss, transform, entity) in boss_query.iter_mut() {
+        if !enemy.is_alive() {
+            continue;
+        }
+        let archetype = &bestiary.archetypes[enemy.archetype()];
+        let phase = archetype.boss_phase(boss.health_fraction(enemy));
+        if phase != boss.phase {
+            info!("{} enters phase {}", archetype.name, phase + 1);
+            boss.phase = phase;
+            boss.next_charge = None;
+            boss.next_summon = None;
+        }
+        let phase = &archetype.boss_phases[phase];
+        let position = transform.translation.truncate();
+
+        if let Some(charge) = &phase.charge {
+            let interval = Duration::from_secs_f32(charge.every_secs.max(0.0));
+            match boss.next_charge {
+                None => boss.next_charge = Some(now + interval),
+                Some(next) if now >= next => {
+                    let toward =
+                        (player_transform.translation.truncate() - position).normalize_or_zero();
+                    commands
+                        .entity(entity)
+                        .insert(Velocity::linear(toward * charge.speed));
+                    boss.next_charge = Some(now + interval);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if let Some(summon) = &phase.summon {
+            let interval = Duration::from_secs_f32(summon.every_secs.max(0.0));
+            match boss.next_summon {
+                None => boss.next_summon = Some(now + interval),
+                Some(next) if now >= next => {
+                    // Validation made sure the name exists.
+                    let minion = bestiary.index_of(&summon.archetype).unwrap_or(0);
+                    for _ in 0..summon.count {
+                        let angle = rng.f32() * std::f32::consts::TAU;
+                        let offset = Vec2::from_angle(angle) * archetype.radius * 2.0;
+                        spawn_telegraph(
+                            &mut commands,
+                            minion,
+                            (position + offset).clamp(-ARENA_HALF_SIZE, ARENA_HALF_SIZE),
+                            now,
+                            stages.spawns.telegraph(),
+                        );
+                    }
+                    boss.next_summon = Some(now + interval);
+                }
+                Some(_) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{settings::LaunchSettings, states::AppState};
+use bevy::{prelude::*, utils::Duration};
+
+use super::FixedSet;
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = app.world.resource::<LaunchSettings>();
+        let clock = GameClock {
+            elapsed: Duration::ZERO,
+            step: Duration::from_secs_f64(1.0 / settings.tick_rate as f64),
+            scale: settings.time_scale,
+        };
+
+        app.insert_resource(FixedTime::new(clock.real_period()));
+        app.insert_resource(clock);
+        app.add_system(apply_time_scale.in_base_set(CoreSet::First));
+        app.add_system(
+            tick_game_clock
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .in_base_set(FixedSet::Input)
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// Time as seen by gameplay. Advances by one `step` per fixed update while in
+/// `AppState::InGame`, so pausing or sitting in a cutscene doesn't eat into
+/// stage timers. `scale` changes how many steps run per real second.
+#[derive(Resource, Debug)]
+pub struct GameClock {
+    elapsed: Duration,
+    step: Duration,
+    pub scale: f32,
+}
+
+impl GameClock {
+    /// Game time since launch.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Game time covered by one fixed update.
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    fn real_period(&self) -> Duration {
+        self.step.div_f32(self.scale.max(0.01))
+    }
+}
+
+fn tick_game_clock(mut clock: ResMut<GameClock>) {
+    let step = clock.step;
+    clock.elapsed += step;
+}
+
+fn apply_time_scale(clock: Res<GameClock>, mut fixed_time: ResMut<FixedTime>) {
+    if clock.is_changed() && fixed_time.period != clock.real_period() {
+        fixed_time.period = clock.real_period();
+    }
+}
@@ -0,0 +1,85 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+/// Gameplay data designers edit as a RON file under `assets/`. Loaded as an
+/// asset in `AppState::Loading` and then copied into a resource, so the
+/// simulation can use it without an `AssetServer`.
+pub trait DataAsset: TypeUuid + DeserializeOwned + Resource + Clone {
+    /// Relative to the assets folder. Everything after the first dot of the
+    /// file name is the extension the loader claims.
+    const PATH: &'static str;
+
+    /// Checks what the file format can't express.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn parse(bytes: &[u8]) -> Result<Self, bevy::asset::Error> {
+        let data: Self = ron::de::from_bytes(bytes)?;
+        data.validate()
+            .map_err(|err| bevy::asset::Error::msg(format!("{}: {err}", Self::PATH)))?;
+        Ok(data)
+    }
+
+    /// Reads `PATH` straight from disk, for running without an `AssetServer`.
+    fn read_from_disk() -> Result<Self, bevy::asset::Error> {
+        let path = asset_root().join(Self::PATH);
+        let bytes = fs::read(&path).map_err(|err| {
+            bevy::asset::Error::msg(format!("could not read {}: {err}", path.display()))
+        })?;
+        Self::parse(&bytes)
+    }
+}
+
+/// Where `AssetServer` looks for files.
+fn asset_root() -> PathBuf {
+    #[cfg(not(target_arch = "wasm32"))]
+    return bevy::asset::FileAssetIo::get_base_path().join("assets");
+    #[cfg(target_arch = "wasm32")]
+    return PathBuf::from("assets");
+}
+
+pub struct DataAssetLoader<T> {
+    extensions: [&'static str; 1],
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DataAsset> Default for DataAssetLoader<T> {
+    fn default() -> Self {
+        let file_name = Path::new(T::PATH)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(T::PATH);
+        let extension = file_name
+            .split_once('.')
+            .map_or(file_name, |(_, extension)| extension);
+
+        Self {
+            extensions: [extension],
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: DataAsset> AssetLoader for DataAssetLoader<T> {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            load_context.set_default_asset(LoadedAsset::new(T::parse(bytes)?));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
@@ -1,31 +1,94 @@
-use crate::{loading::AudioHandles, states::AppState};
+use crate::{settings::LaunchSettings, states::AppState};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use bevy::utils::Duration;
 use bevy_rapier2d::prelude::*;
 use bevy_turborand::prelude::*;
-use instant::{Duration, Instant};
 
+use super::awareness::{update_enemy_states, EnemyState, BITE_STUN};
+use super::bestiary::{Bestiary, EnemyAi, EnemySprite};
+use super::boss::Boss;
+use super::clock::GameClock;
+use super::events::{
+    DamageDealt, EnemyKilled, HitCause, PlayerHit, SpitShotDown, StageCleared, StageStarted,
+};
+use super::pathfinding::{update_flow_field, FlowField};
+use super::physics::{PLAYER_GROUP, SPIT_GROUP};
+use super::pickups::{roll_drop, spawn_pickup, DropTables};
 use super::player::{Bullet, Player};
+use super::projectiles::{Spit, Spitter};
+use super::spatial::{rebuild_enemy_grid, EnemyGrid};
+use super::stages::{MixEntry, SpawnAt, SpawnRules, Stage, Stages};
+use super::steering::steer;
+use super::{FixedSet, RngSet};
 
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(spawn_enemy_spawner.in_schedule(OnEnter(AppState::InGame)));
-        app.add_system(spawn_enemies.in_set(OnUpdate(AppState::InGame)));
-        app.add_system(start_stage.in_set(OnUpdate(AppState::InGame)));
+        app.add_systems(
+            (
+                spawn_enemies.in_set(RngSet::Spawns),
+                spawn_stress_crowd
+                    .after(spawn_enemies)
+                    .in_set(RngSet::Spawns),
+                materialize_enemies,
+                start_stage,
+                handle_enemy_death,
+                // Sees last step's spawns and splits, which this step's
+                // commands haven't added yet.
+                check_for_stage_end
+                    .before(spawn_enemies)
+                    .before(handle_enemy_death),
+                move_enemies_toward_player
+                    .after(update_flow_field)
+                    .after(rebuild_enemy_grid)
+                    .after(update_enemy_states),
+            )
+                .in_base_set(FixedSet::Update)
+                .distributive_run_if(in_state(AppState::InGame))
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
+        app.add_system(
+            drop_stage_reward
+                .after(check_for_stage_end)
+                .in_set(RngSet::StageReward)
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .in_base_set(FixedSet::Update)
+                .run_if(in_state(AppState::InGame)),
+        );
         app.add_system(
             display_collision_events
-                .after(move_enemies_toward_player)
-                .in_set(OnUpdate(AppState::InGame)),
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .in_base_set(FixedSet::PostPhysics)
+                .run_if(in_state(AppState::InGame)),
         );
-        app.add_system(handle_enemy_death.in_set(OnUpdate(AppState::InGame)));
-        app.add_system(check_for_stage_end.in_set(OnUpdate(AppState::InGame)));
         app.add_system(
-            animate_enemies
-                .after(handle_enemy_death)
-                .in_set(OnUpdate(AppState::InGame)),
+            bite_player
+                .after(display_collision_events)
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .in_base_set(FixedSet::PostPhysics)
+                .run_if(in_state(AppState::InGame)),
+        );
+        app.add_systems(
+            (apply_damage, drop_remains, keep_score)
+                .in_base_set(FixedSet::Reactions)
+                .distributive_run_if(in_state(AppState::InGame))
+                .in_schedule(CoreSchedule::FixedUpdate),
         );
-        app.add_system(move_enemies_toward_player.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+/// Sprites and animation for enemies.
+pub struct EnemySpritePlugin;
+
+impl Plugin for EnemySpritePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(attach_enemy_sprite.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(animate_enemies.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(attach_telegraph_sprite.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(fade_in_telegraphs.in_set(OnUpdate(AppState::InGame)));
     }
 }
 
@@ -33,22 +96,97 @@ impl Plugin for EnemyPlugin {
 pub struct EnemySpawner {
     pub stage: u32,
     started: bool,
-    start_time: Instant,
+    start_time: Duration,
     pub score: i32,
+    /// What `GlobalRng` was seeded with when the run started.
+    pub seed: u64,
+    /// Game time when the run started.
+    pub run_start: Duration,
+    /// The current stage's waves and rewards.
+    plan: Stage,
+    /// Enemies spawned so far by each of the plan's waves.
+    spawned: Vec<u32>,
+}
+
+impl EnemySpawner {
+    fn begin_stage(&mut self, stage: u32, stages: &Stages, start_time: Duration) {
+        self.stage = stage;
+        self.started = false;
+        self.start_time = start_time;
+        self.plan = stages.get(stage);
+        self.spawned = vec![0; self.plan.waves.len()];
+    }
+
+    fn all_spawned(&self) -> bool {
+        self.plan
+            .waves
+            .iter()
+            .zip(&self.spawned)
+            .all(|(wave, spawned)| *spawned >= wave.count)
+    }
 }
 
 #[derive(Component)]
 pub struct Enemy {
     health: i32,
-    state: EnemyState,
+    pub state: EnemyState,
+    /// Index into the `Bestiary`.
+    archetype: usize,
 }
 
-#[derive(PartialEq)]
-enum EnemyState {
-    Zombie,
-    Destroyed,
+impl Enemy {
+    pub fn health(&self) -> i32 {
+        self.health
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.state != EnemyState::Dead
+    }
+
+    /// Being shot gives the player away to a wandering enemy at once.
+    fn take_damage(&mut self, damage: i32) {
+        self.health -= damage;
+        if let EnemyState::Wandering { .. } = self.state {
+            self.state = EnemyState::Alerted {
+                until: Duration::ZERO,
+            };
+        }
+    }
+
+    /// Index into the `Bestiary`.
+    pub fn archetype(&self) -> usize {
+        self.archetype
+    }
+}
+
+/// Marks where an enemy is about to appear.
+#[derive(Component)]
+pub struct SpawnTelegraph {
+    archetype: usize,
+    /// Game time the marker appeared.
+    shown: Duration,
+    /// Game time the enemy appears.
+    arrives: Duration,
 }
 
+/// Inside the arena walls, with room for the biggest enemies.
+pub const ARENA_HALF_SIZE: Vec2 = Vec2::new(370.0, 270.0);
+/// Random spots tried for being far enough from the player before falling
+/// back to one that is.
+const SPAWN_TRIES: usize = 8;
+
+/// On enemies touching the player, between their collision starting and
+/// stopping.
+#[derive(Component)]
+pub struct Biting;
+
+/// How long the player can't be bitten again after a bite.
+const INVULNERABILITY: Duration = Duration::from_millis(800);
+/// Pushes a biting enemy away from the player.
+const KNOCKBACK_IMPULSE: f32 = 4.0;
+/// Fraction of its speed an enemy wanders at.
+const WANDER_THROTTLE: f32 = 0.4;
+
 #[derive(Component)]
 struct AnimationIndices {
     first: usize,
@@ -58,41 +196,91 @@ struct AnimationIndices {
 #[derive(Component, Deref, DerefMut)]
 struct AnimationTimer(Timer);
 
-fn spawn_enemy_spawner(mut commands: Commands, enemy_spawner_query: Query<&EnemySpawner>) {
-    for _ in enemy_spawner_query.iter() {
+fn spawn_enemy_spawner(
+    mut commands: Commands,
+    enemy_spawner_query: Query<&EnemySpawner>,
+    settings: Res<LaunchSettings>,
+    mut rng: ResMut<GlobalRng>,
+    clock: Res<GameClock>,
+    stages: Res<Stages>,
+) {
+    if !enemy_spawner_query.is_empty() {
         return;
     }
 
-    commands.spawn(EnemySpawner {
-        stage: 1,
+    // Reseed at the start of every run so the seed alone reproduces it.
+    let seed = settings.seed.unwrap_or_else(|| rng.u64(..));
+    rng.reseed(seed);
+    info!("Starting run with seed {seed} at stage {}", settings.stage);
+
+    let mut spawner = EnemySpawner {
+        stage: settings.stage,
         started: false,
-        start_time: instant::Instant::now(),
+        start_time: clock.elapsed(),
         score: 0,
-    });
+        seed,
+        run_start: clock.elapsed(),
+        plan: Stage::endless(settings.stage),
+        spawned: Vec::new(),
+    };
+    spawner.begin_stage(settings.stage, &stages, clock.elapsed());
+    commands.spawn(spawner);
 }
 
-fn start_stage(mut enemy_spawner_query: Query<&mut EnemySpawner>) {
-    for mut spawner in enemy_spawner_query.iter_mut() {
-        if !spawner.started {
-            if instant::Instant::now() > spawner.start_time {
-                spawner.started = true;
-            }
-        }
+/// Drops `--stress` enemies all over the arena as a run starts, on top of
+/// the stage's own.
+fn spawn_stress_crowd(
+    mut commands: Commands,
+    new_spawner_query: Query<&EnemySpawner, Added<EnemySpawner>>,
+    player_query: Query<&Transform, With<Player>>,
+    settings: Res<LaunchSettings>,
+    mut rng: ResMut<GlobalRng>,
+    bestiary: Res<Bestiary>,
+    stages: Res<Stages>,
+) {
+    let Ok(spawner) = new_spawner_query.get_single() else {
+        return;
+    };
+    if settings.stress == 0 {
+        return;
     }
-}
 
-fn get_current_ideal_enemy_count(stage: u32, time_since_start: Duration) -> usize {
-    const MAX_DURATION_SECS: f32 = 30.0;
-    let max_enemies_for_state = get_max_enemy_count(stage);
-    let percent_duration_remaining = (time_since_start.as_secs() as f32) / MAX_DURATION_SECS;
-
-    let ideal_cound_float = max_enemies_for_state as f32 * percent_duration_remaining;
+    info!("Dropping {} enemies in for a stress test", settings.stress);
+    let player = player_query
+        .get_single()
+        .ok()
+        .map(|transform| transform.translation.truncate());
+    for _ in 0..settings.stress {
+        let archetype = bestiary.pick(spawner.stage, &mut rng);
+        let position = pick_spawn_position(&SpawnAt::Anywhere, &stages.spawns, player, &mut rng);
+        spawn_enemy(
+            &mut commands,
+            &bestiary,
+            archetype,
+            position,
+            EnemyState::arriving(position),
+        );
+    }
+}
 
-    ideal_cound_float.ceil() as usize
+fn start_stage(
+    mut enemy_spawner_query: Query<&mut EnemySpawner>,
+    clock: Res<GameClock>,
+    mut stage_started_events: EventWriter<StageStarted>,
+) {
+    for mut spawner in enemy_spawner_query.iter_mut() {
+        if !spawner.started && clock.elapsed() >= spawner.start_time {
+            spawner.started = true;
+            stage_started_events.send(StageStarted {
+                stage: spawner.stage,
+            });
+        }
+    }
 }
 
 fn animate_enemies(
     time: Res<Time>,
+    clock: Res<GameClock>,
     mut query: Query<(
         &AnimationIndices,
         &Enemy,
@@ -102,11 +290,11 @@ fn animate_enemies(
     )>,
 ) {
     for (indices, enemy, force, mut timer, mut sprite) in &mut query {
-        if enemy.state == EnemyState::Destroyed {
+        if !enemy.is_alive() {
             sprite.index = 0;
             continue;
         }
-        timer.tick(time.delta());
+        timer.tick(time.delta().mul_f32(clock.scale));
         if timer.just_finished() {
             if (force.force.x > 0.1 || force.force.x < -0.1)
                 || (force.force.y > 0.1 || force.force.y < -0.1)
@@ -117,63 +305,210 @@ fn animate_enemies(
                     sprite.index + 1
                 };
             } else {
-                sprite.index = 1;
+                sprite.index = indices.first;
             }
         }
     }
 }
 
-fn get_max_enemy_count(stage: u32) -> usize {
-    stage as usize * 10
+/// Spawns at most one enemy a step, from the first wave that is behind.
+fn spawn_enemies(
+    mut commands: Commands,
+    mut enemy_spawner_query: Query<&mut EnemySpawner>,
+    player_query: Query<&Transform, With<Player>>,
+    mut rng: ResMut<GlobalRng>,
+    clock: Res<GameClock>,
+    bestiary: Res<Bestiary>,
+    stages: Res<Stages>,
+) {
+    let Ok(mut spawner) = enemy_spawner_query.get_single_mut() else {
+        return;
+    };
+    if !spawner.started {
+        return;
+    }
+
+    let since_stage_start = clock.elapsed().saturating_sub(spawner.start_time);
+    let behind = spawner
+        .plan
+        .waves
+        .iter()
+        .zip(&spawner.spawned)
+        .position(|(wave, spawned)| *spawned < wave.due(since_stage_start));
+    let Some(wave) = behind else {
+        return;
+    };
+    spawner.spawned[wave] += 1;
+
+    let stage = spawner.stage;
+    let wave = &spawner.plan.waves[wave];
+    let archetype = pick_archetype(&wave.mix, stage, &bestiary, &mut rng);
+    let player = player_query
+        .get_single()
+        .ok()
+        .map(|transform| transform.translation.truncate());
+    let position = pick_spawn_position(&wave.spawn_at, &stages.spawns, player, &mut rng);
+    spawn_telegraph(
+        &mut commands,
+        archetype,
+        position,
+        clock.elapsed(),
+        stages.spawns.telegraph(),
+    );
 }
 
-fn spawn_enemies(
-    commands: Commands,
-    enemy_query: Query<&Enemy>,
-    enemy_spawner_query: Query<&EnemySpawner>,
-    global_rng: ResMut<GlobalRng>,
-    asset_server: Res<AssetServer>,
-    texture_atlases: ResMut<Assets<TextureAtlas>>,
+/// Shows where `archetype` is going to appear after `telegraph`.
+pub fn spawn_telegraph(
+    commands: &mut Commands,
+    archetype: usize,
+    position: Vec2,
+    now: Duration,
+    telegraph: Duration,
 ) {
-    if let Some(spawner) = enemy_spawner_query.iter().next() {
-        if !spawner.started {
-            return;
+    commands
+        .spawn(SpawnTelegraph {
+            archetype,
+            shown: now,
+            arrives: now + telegraph,
+        })
+        .insert(TransformBundle::from(Transform::from_xyz(
+            position.x, position.y, 0.5,
+        )));
+}
+
+/// Picks a spot for a wave's enemy at least `min_distance` from the player.
+/// If none of `SPAWN_TRIES` random spots is, takes the farthest of the wave's
+/// named points, or failing that the wall opposite the player, which
+/// `Stages::validate` keeps far enough.
+fn pick_spawn_position(
+    at: &SpawnAt,
+    rules: &SpawnRules,
+    player: Option<Vec2>,
+    rng: &mut GlobalRng,
+) -> Vec2 {
+    let points: Vec<Vec2> = match at {
+        SpawnAt::Points(names) => names.iter().filter_map(|name| rules.point(name)).collect(),
+        _ => Vec::new(),
+    };
+    let mut candidate = || match at {
+        SpawnAt::Points(_) if !points.is_empty() => points[rng.usize(0..points.len())],
+        SpawnAt::Anywhere => {
+            Vec2::new(rng.f32_normalized(), rng.f32_normalized()) * ARENA_HALF_SIZE
+        }
+        _ => {
+            let along = rng.f32_normalized();
+            let edge = match rng.u32(0..4) {
+                0 => Vec2::new(-1.0, along),
+                1 => Vec2::new(1.0, along),
+                2 => Vec2::new(along, 1.0),
+                _ => Vec2::new(along, -1.0),
+            };
+            edge * ARENA_HALF_SIZE
         }
+    };
 
-        let time_since_stage_start = instant::Instant::now().duration_since(spawner.start_time);
+    let Some(player) = player else {
+        return candidate();
+    };
+    let far_enough = |position: &Vec2| position.distance(player) >= rules.min_distance;
+    if let Some(position) = (0..SPAWN_TRIES).map(|_| candidate()).find(far_enough) {
+        return position;
+    }
+
+    let farthest_point = points
+        .iter()
+        .copied()
+        .max_by(|a, b| a.distance(player).total_cmp(&b.distance(player)));
+    match farthest_point {
+        Some(point) if far_enough(&point) => point,
+        _ => opposite_wall(player),
+    }
+}
 
-        let ideal_enemy_count =
-            get_current_ideal_enemy_count(spawner.stage, time_since_stage_start);
-        let current_enemy_count = enemy_query.iter().len();
+/// Where the line from the player through the middle of the arena meets the
+/// far wall. At least the arena's shorter half size from the player.
+fn opposite_wall(player: Vec2) -> Vec2 {
+    let away = if player.length_squared() > f32::EPSILON {
+        -player
+    } else {
+        Vec2::X
+    };
+    away / (away / ARENA_HALF_SIZE).abs().max_element()
+}
 
-        if current_enemy_count >= get_max_enemy_count(spawner.stage) {
-            return;
+/// Replaces telegraphs whose time is up with their enemy.
+fn materialize_enemies(
+    mut commands: Commands,
+    telegraph_query: Query<(&SpawnTelegraph, &Transform, Entity)>,
+    player_query: Query<&Transform, With<Player>>,
+    clock: Res<GameClock>,
+    bestiary: Res<Bestiary>,
+) {
+    for (telegraph, transform, entity) in telegraph_query.iter() {
+        if clock.elapsed() < telegraph.arrives {
+            continue;
         }
 
-        if current_enemy_count < ideal_enemy_count {
-            spawn_enemy(commands, global_rng, asset_server, texture_atlases);
+        let position = transform.translation.truncate();
+        if let Ok(player_transform) = player_query.get_single() {
+            debug!(
+                "{} appears {:.0} from the player",
+                bestiary.archetypes[telegraph.archetype].name,
+                position.distance(player_transform.translation.truncate())
+            );
         }
+        // Bosses come straight for the player.
+        let state = if bestiary.archetypes[telegraph.archetype].is_boss() {
+            EnemyState::chasing(clock.elapsed())
+        } else {
+            EnemyState::arriving(position)
+        };
+        spawn_enemy(
+            &mut commands,
+            &bestiary,
+            telegraph.archetype,
+            position,
+            state,
+        );
+        commands.entity(entity).despawn_recursive();
     }
 }
 
+/// Picks from a wave's mix by weight, or from the bestiary's archetypes for
+/// the stage when the mix is empty.
+fn pick_archetype(mix: &[MixEntry], stage: u32, bestiary: &Bestiary, rng: &mut GlobalRng) -> usize {
+    let total: u32 = mix.iter().map(|entry| entry.weight).sum();
+    if total == 0 {
+        return bestiary.pick(stage, rng);
+    }
+
+    let mut roll = rng.u32(0..total);
+    for entry in mix {
+        if roll < entry.weight {
+            return bestiary.index_of(&entry.archetype).unwrap_or_else(|| {
+                warn!("stage {stage} spawns unknown archetype {}", entry.archetype);
+                bestiary.pick(stage, rng)
+            });
+        }
+        roll -= entry.weight;
+    }
+    bestiary.pick(stage, rng)
+}
+
 fn spawn_enemy(
-    mut commands: Commands,
-    mut rng: ResMut<GlobalRng>,
-    asset_server: Res<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    commands: &mut Commands,
+    bestiary: &Bestiary,
+    archetype: usize,
+    position: Vec2,
+    state: EnemyState,
 ) {
-    let texture_handle = asset_server.load("sprites/zombiebaby.png");
-    let texture_atlas =
-        TextureAtlas::from_grid(texture_handle, Vec2::new(64.0, 64.0), 1, 3, None, None);
-    let texture_atlas_handle = texture_atlases.add(texture_atlas);
-    // Use only the subset of sprites in the sheet that make up the run animation
-    let animation_indices = AnimationIndices { first: 1, last: 2 };
-
-    commands
-        .spawn(RigidBody::Dynamic)
+    let stats = &bestiary.archetypes[archetype];
+    let mut enemy = commands.spawn(RigidBody::Dynamic);
+    enemy
         .insert(Enemy {
-            health: 100,
-            state: EnemyState::Zombie,
+            health: stats.health,
+            state,
+            archetype,
         })
         .insert(Sleeping::disabled())
         .insert(Ccd::enabled())
@@ -181,190 +516,567 @@ fn spawn_enemy(
             force: Vec2::new(0.0, 0.0),
             torque: 0.0,
         })
-        .insert(Collider::ball(10.0))
-        .insert(ColliderMassProperties::Density(0.8))
+        .insert(Collider::ball(stats.radius))
+        .insert(ColliderMassProperties::Density(stats.density))
         .insert(ActiveEvents::COLLISION_EVENTS)
-        .insert(CollisionGroups::new(Group::ALL, Group::ALL))
-        .insert((
-            SpriteSheetBundle {
-                texture_atlas: texture_atlas_handle,
-                sprite: TextureAtlasSprite::new(animation_indices.first),
+        .insert(CollisionGroups::new(
+            Group::ALL.difference(PLAYER_GROUP),
+            Group::ALL.difference(SPIT_GROUP),
+        ))
+        .insert(TransformBundle::from(Transform::from_xyz(
+            position.x, position.y, 1.0,
+        )));
+    if stats.is_boss() {
+        enemy.insert(Boss::new(stats.health));
+    }
+    if let EnemyAi::Ranged { spit: Some(_), .. } = stats.ai {
+        enemy.insert(Spitter::default());
+    }
+}
+
+fn attach_enemy_sprite(
+    mut commands: Commands,
+    enemy_query: Query<(Entity, &Enemy), Added<Enemy>>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    bestiary: Res<Bestiary>,
+) {
+    for (entity, enemy) in enemy_query.iter() {
+        let sprite = &bestiary.archetypes[enemy.archetype].sprite;
+        let texture_atlas_handle = load_enemy_atlas(sprite, &asset_server, &mut texture_atlases);
+        // Use only the subset of sprites in the sheet that make up the run animation
+        let animation_indices = AnimationIndices {
+            first: sprite.run.0,
+            last: sprite.run.1,
+        };
+
+        commands.entity(entity).insert((
+            texture_atlas_handle,
+            TextureAtlasSprite {
+                index: animation_indices.first,
+                color: sprite.tint(),
+                custom_size: Some(Vec2::splat(sprite.size)),
                 ..default()
             },
+            VisibilityBundle::default(),
             animation_indices,
             AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-        ))
-        .insert(TransformBundle::from(Transform::from_xyz(
-            rng.i32(-390..=390) as f32,
-            rng.i32(-290..=290) as f32,
-            1.0,
-        )));
+        ));
+    }
+}
+
+fn load_enemy_atlas(
+    sprite: &EnemySprite,
+    asset_server: &AssetServer,
+    texture_atlases: &mut Assets<TextureAtlas>,
+) -> Handle<TextureAtlas> {
+    let texture_handle = asset_server.load(sprite.sheet.as_str());
+    texture_atlases.add(TextureAtlas::from_grid(
+        texture_handle,
+        Vec2::new(sprite.frame.0, sprite.frame.1),
+        sprite.columns,
+        sprite.rows,
+        None,
+        None,
+    ))
+}
+
+/// Telegraphs show the coming enemy as a shadow.
+fn attach_telegraph_sprite(
+    mut commands: Commands,
+    telegraph_query: Query<(Entity, &SpawnTelegraph), Added<SpawnTelegraph>>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    bestiary: Res<Bestiary>,
+) {
+    for (entity, telegraph) in telegraph_query.iter() {
+        let sprite = &bestiary.archetypes[telegraph.archetype].sprite;
+        commands.entity(entity).insert((
+            load_enemy_atlas(sprite, &asset_server, &mut texture_atlases),
+            TextureAtlasSprite {
+                index: sprite.run.0,
+                color: Color::rgba(0.0, 0.0, 0.0, 0.0),
+                custom_size: Some(Vec2::splat(sprite.size)),
+                ..default()
+            },
+            VisibilityBundle::default(),
+        ));
+    }
+}
+
+/// Darkens the shadow as the enemy's arrival nears.
+fn fade_in_telegraphs(
+    mut telegraph_query: Query<(&SpawnTelegraph, &mut TextureAtlasSprite)>,
+    clock: Res<GameClock>,
+) {
+    for (telegraph, mut sprite) in telegraph_query.iter_mut() {
+        let total = telegraph.arrives.saturating_sub(telegraph.shown);
+        let progress = if total.is_zero() {
+            1.0
+        } else {
+            clock
+                .elapsed()
+                .saturating_sub(telegraph.shown)
+                .as_secs_f32()
+                / total.as_secs_f32()
+        };
+        sprite.color.set_a(0.6 * progress.min(1.0));
+    }
+}
+
+/// Everything collision events are routed between.
+#[derive(SystemParam)]
+struct Colliders<'w, 's> {
+    players: Query<'w, 's, &'static mut Player>,
+    enemies: Query<'w, 's, &'static mut Enemy>,
+    bullets: Query<'w, 's, &'static mut Bullet>,
+    spit: Query<'w, 's, &'static mut Spit>,
 }
 
 fn display_collision_events(
     mut collision_events: EventReader<CollisionEvent>,
-    //mut commands: Commands,
-    mut player_query: Query<&mut Player>,
-    mut enemy_query: Query<&mut Enemy>,
-    mut bullet_query: Query<&mut Bullet>,
-    audio_handles: Res<AudioHandles>,
-    audio: Res<Audio>,
+    mut commands: Commands,
+    mut colliders: Colliders,
+    mut damage_events: EventWriter<DamageDealt>,
+    mut player_hit_events: EventWriter<PlayerHit>,
+    mut spit_shot_down_events: EventWriter<SpitShotDown>,
+    clock: Res<GameClock>,
 ) {
     for collision_event in collision_events.iter() {
         match collision_event {
             CollisionEvent::Started(first, second, _) => {
-                let player_entity_opt = get_collided_player(first, second, &player_query);
-                let enemy_entity_opt = get_collided_enemy(first, second, &enemy_query);
-                let bullet_entity_opt = get_collided_bullet(first, second, &bullet_query);
+                let player_entity_opt = get_collided(first, second, &colliders.players);
+                let enemy_entity_opt = get_collided(first, second, &colliders.enemies);
+                let bullet_entity_opt = get_collided(first, second, &colliders.bullets);
+                let spit_entity_opt = get_collided(first, second, &colliders.spit);
 
-                if let (Some(player_entity), Some(_)) = (player_entity_opt, enemy_entity_opt) {
-                    // Something is confirmed started colliding with player.
-                    let player_res = player_query.get_mut(player_entity);
+                if let (Some(_), Some(enemy_entity)) = (player_entity_opt, enemy_entity_opt) {
+                    // Bites happen in `bite_player` for as long as they touch.
+                    commands.entity(enemy_entity).insert(Biting);
+                }
 
-                    if let Ok(mut player) = player_res {
-                        player.health -= 10;
-                        audio.play(Handle::weak(audio_handles.bite.id()));
+                if let (Some(bullet_entity), Some(enemy_entity)) =
+                    (bullet_entity_opt, enemy_entity_opt)
+                {
+                    if let Ok(bullet) = colliders.bullets.get(bullet_entity) {
+                        damage_events.send(DamageDealt {
+                            enemy: enemy_entity,
+                            source: bullet_entity,
+                            amount: bullet.damage,
+                        });
                     }
                 }
 
-                if let (Some(_), Some(enemy_entity)) = (bullet_entity_opt, enemy_entity_opt) {
-                    // Something is confirmed started colliding with player.
-                    let enemy_res = enemy_query.get_mut(enemy_entity);
+                // Spit splashes on the player, pellets and walls alike, and
+                // only the first of them counts.
+                if let Some(spit_entity) = spit_entity_opt {
+                    if let Ok(mut spit) = colliders.spit.get_mut(spit_entity) {
+                        if !spit.collided {
+                            spit.collided = true;
+                            if bullet_entity_opt.is_some() {
+                                spit_shot_down_events.send(SpitShotDown);
+                            }
 
-                    if let Ok(mut enemy) = enemy_res {
-                        enemy.health -= 50;
-                        audio.play(Handle::weak(audio_handles.hit.id()));
+                            if let Some(player_entity) = player_entity_opt {
+                                if let Ok(mut player) = colliders.players.get_mut(player_entity) {
+                                    if clock.elapsed() >= player.invulnerable_until {
+                                        player.invulnerable_until =
+                                            clock.elapsed() + INVULNERABILITY;
+                                        player_hit_events.send(PlayerHit {
+                                            damage: spit.damage,
+                                            cause: HitCause::Spit,
+                                        });
+                                    }
+                                }
+                            }
+                        }
                     }
                 }
 
                 if let Some(bullet_entity) = bullet_entity_opt {
-                    let bullet_res = bullet_query.get_mut(bullet_entity);
+                    let bullet_res = colliders.bullets.get_mut(bullet_entity);
 
                     if let Ok(mut bullet) = bullet_res {
                         bullet.collided = true;
                     }
                 }
             }
-            CollisionEvent::Stopped(_, _, _) => {}
+            CollisionEvent::Stopped(first, second, _) => {
+                let player_entity_opt = get_collided(first, second, &colliders.players);
+                let enemy_entity_opt = get_collided(first, second, &colliders.enemies);
+
+                if let (Some(_), Some(enemy_entity)) = (player_entity_opt, enemy_entity_opt) {
+                    commands.entity(enemy_entity).remove::<Biting>();
+                }
+            }
         }
     }
 }
 
-fn get_collided_player(
-    first_collider: &Entity,
-    second_collider: &Entity,
-    player_query: &Query<&mut Player>,
-) -> Option<Entity> {
-    if let Ok(_) = player_query.get(*first_collider) {
-        return Some(*first_collider);
+/// Enemies whose bite has wound up bite whenever the player isn't
+/// invulnerable from the last bite, and get knocked back and stunned when
+/// they do.
+fn bite_player(
+    mut commands: Commands,
+    mut player_query: Query<(&mut Player, &Transform)>,
+    mut biting_query: Query<(&mut Enemy, &Transform, Entity), With<Biting>>,
+    mut player_hit_events: EventWriter<PlayerHit>,
+    clock: Res<GameClock>,
+    bestiary: Res<Bestiary>,
+) {
+    let Ok((mut player, player_transform)) = player_query.get_single_mut() else {
+        return;
+    };
+    let now = clock.elapsed();
+    if now < player.invulnerable_until {
+        return;
     }
 
-    if let Ok(_) = player_query.get(*second_collider) {
-        return Some(*second_collider);
-    }
+    let wound_up = |enemy: &Enemy| matches!(enemy.state, EnemyState::Attacking { bites_at } if now >= bites_at);
+    // Several enemies biting at once count as the hardest bite among them.
+    let Some(damage) = biting_query
+        .iter()
+        .filter(|(enemy, _, _)| wound_up(enemy))
+        .map(|(enemy, _, _)| bestiary.archetypes[enemy.archetype].bite_damage)
+        .max()
+    else {
+        return;
+    };
+
+    player.invulnerable_until = clock.elapsed() + INVULNERABILITY;
+    player_hit_events.send(PlayerHit {
+        damage,
+        cause: HitCause::Bite,
+    });
 
-    return None;
+    for (mut enemy, enemy_transform, entity) in biting_query.iter_mut() {
+        if !wound_up(&enemy) {
+            continue;
+        }
+        enemy.state = EnemyState::Stunned {
+            until: now + BITE_STUN,
+        };
+        let away = (enemy_transform.translation - player_transform.translation)
+            .truncate()
+            .normalize_or_zero();
+        commands.entity(entity).insert(ExternalImpulse {
+            impulse: away * KNOCKBACK_IMPULSE,
+            torque_impulse: 0.0,
+        });
+    }
 }
 
-fn get_collided_enemy(
+/// Whichever of the two colliders `query` matches.
+fn get_collided<C: Component>(
     first_collider: &Entity,
     second_collider: &Entity,
-    enemy_query: &Query<&mut Enemy>,
+    query: &Query<&mut C>,
 ) -> Option<Entity> {
-    if let Ok(_) = enemy_query.get(*first_collider) {
+    if query.get(*first_collider).is_ok() {
         return Some(*first_collider);
     }
 
-    if let Ok(_) = enemy_query.get(*second_collider) {
+    if query.get(*second_collider).is_ok() {
         return Some(*second_collider);
     }
 
-    return None;
+    None
 }
 
-fn get_collided_bullet(
-    first_collider: &Entity,
-    second_collider: &Entity,
-    bullet_query: &Query<&mut Bullet>,
-) -> Option<Entity> {
-    if let Ok(_) = bullet_query.get(*first_collider) {
-        return Some(*first_collider);
+/// Takes pellet damage off the enemies hit.
+fn apply_damage(mut damage_events: EventReader<DamageDealt>, mut enemy_query: Query<&mut Enemy>) {
+    for damage in damage_events.iter() {
+        if let Ok(mut enemy) = enemy_query.get_mut(damage.enemy) {
+            enemy.take_damage(damage.amount);
+        }
     }
+}
 
-    if let Ok(_) = bullet_query.get(*second_collider) {
-        return Some(*second_collider);
+fn handle_enemy_death(
+    mut commands: Commands,
+    mut enemy_query: Query<(&mut Enemy, &Transform, Entity)>,
+    mut enemy_killed_events: EventWriter<EnemyKilled>,
+) {
+    for (mut enemy, transform, entity) in enemy_query.iter_mut() {
+        if enemy.health <= 0 && enemy.is_alive() {
+            commands.entity(entity).remove::<Collider>();
+            commands.entity(entity).remove::<RigidBody>();
+            enemy.state = EnemyState::Dead;
+            enemy_killed_events.send(EnemyKilled {
+                archetype: enemy.archetype,
+                position: transform.translation.truncate(),
+            });
+        }
     }
+}
 
-    return None;
+/// Kills and cleared stages add to the score.
+fn keep_score(
+    mut enemy_spawner_query: Query<&mut EnemySpawner>,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    mut stage_cleared_events: EventReader<StageCleared>,
+    bestiary: Res<Bestiary>,
+    stages: Res<Stages>,
+) {
+    let Ok(mut enemy_spawner) = enemy_spawner_query.get_single_mut() else {
+        return;
+    };
+    for killed in enemy_killed_events.iter() {
+        enemy_spawner.score += bestiary.archetypes[killed.archetype].score;
+    }
+    for cleared in stage_cleared_events.iter() {
+        enemy_spawner.score += stages.get(cleared.stage).bonus;
+    }
 }
 
-fn handle_enemy_death(
+/// Killed enemies leave a drop and, for splitters, smaller enemies behind.
+fn drop_remains(
     mut commands: Commands,
-    mut enemy_query: Query<(&mut Enemy, Entity)>,
-    mut enemy_spawner_query: Query<&mut EnemySpawner>,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    drop_tables: Res<DropTables>,
+    mut rng: ResMut<GlobalRng>,
+    clock: Res<GameClock>,
+    bestiary: Res<Bestiary>,
 ) {
-    if let Ok(mut enemy_spawner) = enemy_spawner_query.get_single_mut() {
-        for (mut enemy, entity) in enemy_query.iter_mut() {
-            if enemy.health <= 0 && enemy.state == EnemyState::Zombie {
-                commands.entity(entity).remove::<Collider>();
-                commands.entity(entity).remove::<RigidBody>();
-                enemy.state = EnemyState::Destroyed;
-                enemy_spawner.score += 50;
+    for killed in enemy_killed_events.iter() {
+        let position = killed.position;
+
+        if let Some(kind) = roll_drop(&drop_tables.enemy, &mut rng) {
+            spawn_pickup(
+                &mut commands,
+                kind,
+                position,
+                clock.elapsed() + drop_tables.lifetime(),
+            );
+        }
+
+        let archetype = &bestiary.archetypes[killed.archetype];
+        if let Some(split) = &archetype.split {
+            // Validation made sure the name exists.
+            let into = bestiary.index_of(&split.into).unwrap_or(0);
+            let offset = archetype.radius;
+            for _ in 0..split.count {
+                let scatter =
+                    Vec2::new(rng.f32_normalized() * offset, rng.f32_normalized() * offset);
+                spawn_enemy(
+                    &mut commands,
+                    &bestiary,
+                    into,
+                    position + scatter,
+                    EnemyState::chasing(clock.elapsed()),
+                );
             }
         }
     }
 }
 
+/// A stage is cleared once all its waves have spawned and nothing is left
+/// alive or about to appear, splits included.
+#[allow(clippy::type_complexity)]
 fn check_for_stage_end(
     mut commands: Commands,
-    enemy_query: Query<(&Enemy, Entity)>,
+    enemy_query: Query<(Option<&Enemy>, Entity), Or<(With<Enemy>, With<SpawnTelegraph>)>>,
     mut enemy_spawner_query: Query<&mut EnemySpawner>,
+    clock: Res<GameClock>,
+    stages: Res<Stages>,
+    mut stage_cleared_events: EventWriter<StageCleared>,
 ) {
-    let mut enemy_spawner: Mut<EnemySpawner>;
-    if let Ok(spawner) = enemy_spawner_query.get_single_mut() {
-        enemy_spawner = spawner;
-    } else {
+    let Ok(mut enemy_spawner) = enemy_spawner_query.get_single_mut() else {
         return;
-    }
+    };
 
-    let mut dead_enemies: i32 = 0;
-    for (enemy, _) in enemy_query.iter() {
-        if enemy.state == EnemyState::Destroyed {
-            dead_enemies += 1;
-        }
+    if !enemy_spawner.started
+        || !enemy_spawner.all_spawned()
+        || enemy_query
+            .iter()
+            .any(|(enemy, _)| enemy.is_none_or(Enemy::is_alive))
+    {
+        return;
     }
 
-    if dead_enemies as usize >= get_max_enemy_count(enemy_spawner.stage) {
-        enemy_spawner.started = false;
-        enemy_spawner.stage += 1;
-        enemy_spawner.start_time = Instant::now() + Duration::from_secs(3);
-        info!("Starting new stage: {:?}", enemy_spawner.stage);
+    stage_cleared_events.send(StageCleared {
+        stage: enemy_spawner.stage,
+    });
+
+    let next_start = clock.elapsed() + enemy_spawner.plan.break_length();
+    let next_stage = enemy_spawner.stage + 1;
+    enemy_spawner.begin_stage(next_stage, &stages, next_start);
+    info!("Starting new stage: {:?}", enemy_spawner.stage);
+
+    for (_, entity) in enemy_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
 
-        for (_, entity) in enemy_query.iter() {
-            commands.entity(entity).despawn();
+/// Rolls the cleared stage's reward, or the `stage_clear` drop table, and
+/// drops it somewhere in the arena.
+fn drop_stage_reward(
+    mut commands: Commands,
+    mut stage_cleared_events: EventReader<StageCleared>,
+    clock: Res<GameClock>,
+    drop_tables: Res<DropTables>,
+    stages: Res<Stages>,
+    mut rng: ResMut<GlobalRng>,
+) {
+    for cleared in stage_cleared_events.iter() {
+        let plan = stages.get(cleared.stage);
+        let reward = plan.reward.as_ref().unwrap_or(&drop_tables.stage_clear);
+        if let Some(kind) = roll_drop(reward, &mut rng) {
+            let position = Vec2::new(rng.i32(-300..=300) as f32, rng.i32(-200..=200) as f32);
+            spawn_pickup(
+                &mut commands,
+                kind,
+                position,
+                clock.elapsed() + drop_tables.lifetime(),
+            );
         }
     }
 }
 
 fn move_enemies_toward_player(
-    mut enemy_query: Query<(&mut Enemy, &mut ExternalForce, &mut Transform)>,
+    mut enemy_query: Query<(
+        &mut Enemy,
+        &mut ExternalForce,
+        &mut Transform,
+        Option<&Boss>,
+        Entity,
+    )>,
     player_query: Query<(&Player, &Transform), Without<Enemy>>,
+    bestiary: Res<Bestiary>,
+    flow_field: Res<FlowField>,
+    grid: Res<EnemyGrid>,
 ) {
-    if let Ok((_, player_transform)) = player_query.get_single() {
-        for (enemy, mut external_force, mut enemy_transform) in enemy_query.iter_mut() {
-            if enemy.state == EnemyState::Destroyed {
-                return;
+    let Ok((_, player_transform)) = player_query.get_single() else {
+        return;
+    };
+    let player = player_transform.translation.truncate();
+
+    for (enemy, mut external_force, mut enemy_transform, boss, entity) in enemy_query.iter_mut() {
+        let archetype = &bestiary.archetypes[enemy.archetype];
+        let position = enemy_transform.translation.truncate();
+        let to_player = player - position;
+        let distance = to_player.length();
+
+        let throttle = match (enemy.state, archetype.ai) {
+            (EnemyState::Dead, _) => continue,
+            (EnemyState::Wandering { .. }, _) => WANDER_THROTTLE,
+            (EnemyState::Alerted { .. } | EnemyState::Stunned { .. }, _) => 0.0,
+            (EnemyState::Chasing { .. } | EnemyState::Attacking { .. }, EnemyAi::Chase) => 1.0,
+            // Ranged enemies hold back while they can see the player,
+            // retreating when it comes within three quarters of their range.
+            (EnemyState::Chasing { in_sight: true, .. }, EnemyAi::Ranged { range, .. })
+                if distance < range * 0.75 =>
+            {
+                -1.0
+            }
+            (EnemyState::Chasing { in_sight: true, .. }, EnemyAi::Ranged { range, .. })
+                if distance <= range =>
+            {
+                0.0
             }
-            let mut rads = (enemy_transform.translation.y - player_transform.translation.y)
-                .atan2(enemy_transform.translation.x - player_transform.translation.x);
-            rads = rads + (std::f32::consts::PI / 2.);
-            enemy_transform.rotation = Quat::from_rotation_z(rads);
+            (_, EnemyAi::Ranged { .. }) => 1.0,
+        };
 
-            let enemy_direction_vec = Vec2::from_angle(
-                enemy_transform.rotation.to_euler(EulerRot::XYZ).2 + (std::f32::consts::PI / 2.),
-            );
+        // Follow the flow field around obstacles, straight at the player
+        // once in its cell, and straight back when retreating.
+        let to_player = to_player.normalize_or_zero();
+        let heading = match enemy.state {
+            EnemyState::Wandering { target, .. } => (target - position).normalize_or_zero(),
+            _ if throttle > 0.0 => flow_field.direction(position).unwrap_or(to_player),
+            _ => to_player,
+        };
+        // The sprites face down.
+        if heading != Vec2::ZERO {
+            enemy_transform.rotation =
+                Quat::from_rotation_z(heading.y.atan2(heading.x) - std::f32::consts::FRAC_PI_2);
+        }
+
+        // Only enemies after the player spread out around it.
+        let mut weights = archetype.steering.clone();
+        if !matches!(enemy.state, EnemyState::Chasing { .. }) {
+            weights.surround = 0.0;
+        }
+        let steered = steer(
+            entity,
+            position,
+            enemy.archetype,
+            heading * throttle,
+            player,
+            &weights,
+            &grid,
+        );
+        let speed = match boss {
+            Some(boss) => archetype.speed * archetype.boss_phases[boss.phase].speed,
+            None => archetype.speed,
+        };
+        external_force.force = steered * speed;
+    }
+}
 
-            external_force.force = enemy_direction_vec * 2.0;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::stages::SpawnPoint;
+
+    fn rules() -> SpawnRules {
+        let point = |name: &str, x, y| SpawnPoint {
+            name: name.to_string(),
+            position: (x, y),
+        };
+        SpawnRules {
+            points: vec![
+                point("north door", 0.0, 260.0),
+                point("south door", 0.0, -260.0),
+                point("west corner", -360.0, 250.0),
+                point("east corner", 360.0, -250.0),
+            ],
+            ..default()
         }
     }
+
+    #[test]
+    fn spawns_stay_in_the_arena_and_away_from_the_player() {
+        // As far as `Stages::validate` allows, so the fallbacks get used.
+        let rules = SpawnRules {
+            min_distance: ARENA_HALF_SIZE.min_element(),
+            ..rules()
+        };
+        let spawn_ats = [
+            SpawnAt::Edges,
+            SpawnAt::Anywhere,
+            SpawnAt::Points(vec!["north door".to_string()]),
+            SpawnAt::Points(rules.points.iter().map(|p| p.name.clone()).collect()),
+        ];
+        let players = [
+            Vec2::ZERO,
+            Vec2::new(300.0, 0.0),
+            Vec2::new(0.0, 250.0),
+            Vec2::new(-360.0, -260.0),
+        ];
+
+        for seed in 0..100 {
+            let mut rng = GlobalRng::with_seed(seed);
+            for at in &spawn_ats {
+                for player in players {
+                    let position = pick_spawn_position(at, &rules, Some(player), &mut rng);
+                    assert!(
+                        position.abs().cmple(ARENA_HALF_SIZE + 0.001).all(),
+                        "{at:?} spawned outside the arena at {position}"
+                    );
+                    assert!(
+                        position.distance(player) >= rules.min_distance,
+                        "{at:?} spawned at {position}, too close to the player at {player}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spawns_fall_back_to_the_opposite_wall() {
+        let mut rng = GlobalRng::with_seed(7);
+        let at = SpawnAt::Points(vec!["north door".to_string()]);
+        let position = pick_spawn_position(&at, &rules(), Some(Vec2::new(0.0, 250.0)), &mut rng);
+        assert_eq!(position, Vec2::new(0.0, -ARENA_HALF_SIZE.y));
+    }
 }
@@ -1 +1,78 @@
+use bevy::prelude::*;
 
+use super::pickups::PickupKind;
+
+/// Registers the gameplay events. They are sent during a fixed update and
+/// read by systems in `FixedSet::Reactions`, so each is handled in the step
+/// it happened in, and by the presentation every frame. A reader that has to
+/// act earlier runs in the sender's set, ordered after it, like
+/// `drop_stage_reward` after `check_for_stage_end` in `FixedSet::Update`.
+pub struct GameplayEventsPlugin;
+
+impl Plugin for GameplayEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageDealt>();
+        app.add_event::<EnemyKilled>();
+        app.add_event::<PlayerHit>();
+        app.add_event::<SpitShotDown>();
+        app.add_event::<PickupCollected>();
+        app.add_event::<ShotFired>();
+        app.add_event::<ReloadStarted>();
+        app.add_event::<StageStarted>();
+        app.add_event::<StageCleared>();
+    }
+}
+
+/// A pellet hit an enemy.
+pub struct DamageDealt {
+    pub enemy: Entity,
+    /// The pellet.
+    pub source: Entity,
+    pub amount: i32,
+}
+
+/// An enemy's health ran out.
+pub struct EnemyKilled {
+    /// Index into the `Bestiary`.
+    pub archetype: usize,
+    pub position: Vec2,
+}
+
+/// Something hurt the player, with its invulnerability already started.
+pub struct PlayerHit {
+    pub damage: i32,
+    /// What landed, so stats and sounds can tell bites from spit.
+    pub cause: HitCause,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HitCause {
+    Bite,
+    Spit,
+}
+
+/// A pellet hit a glob before it reached the player.
+pub struct SpitShotDown;
+
+/// The player picked up something they could use.
+pub struct PickupCollected {
+    pub kind: PickupKind,
+}
+
+/// The player fired a round.
+pub struct ShotFired {
+    pub pellets: u32,
+}
+
+/// The player started reloading the weapon in hand.
+pub struct ReloadStarted;
+
+/// A stage's break is over and its waves begin.
+pub struct StageStarted {
+    pub stage: u32,
+}
+
+/// Sent when every enemy of a stage is dead, before the next one begins.
+pub struct StageCleared {
+    pub stage: u32,
+}
@@ -1,16 +1,148 @@
-use crate::states::AppState;
-use bevy::prelude::*;
+use crate::{settings::LaunchSettings, states::AppState};
+use bevy::{
+    input::{
+        mouse::{MouseButtonInput, MouseWheel},
+        ButtonState, InputSystem,
+    },
+    prelude::*,
+};
 
 pub struct InteractPlugin;
 
 impl Plugin for InteractPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(pause.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(
+            read_player_input
+                .after(InputSystem)
+                .in_base_set(CoreSet::PreUpdate)
+                .run_if(in_state(AppState::InGame)),
+        );
+        app.add_system(
+            read_weapon_select
+                .after(InputSystem)
+                .in_base_set(CoreSet::PreUpdate)
+                .run_if(in_state(AppState::InGame)),
+        );
     }
 }
 
+/// What the player asked for this frame. Filled from the keyboard and mouse by
+/// `InteractPlugin`, and read by the simulation instead of the raw devices so
+/// gameplay can run without a window.
+#[derive(Resource, Default, Clone, Debug)]
+pub struct PlayerInput {
+    /// Each axis is -1.0, 0.0 or 1.0.
+    pub movement: Vec2,
+    /// World position of the cursor, set when it moves and cleared once a
+    /// fixed update has used it.
+    pub aim: Option<Vec2>,
+    /// Set on click and cleared once a fixed update has used it.
+    pub fire: bool,
+    /// Whether the fire button is held down, for automatic weapons.
+    pub trigger: bool,
+    pub reload: bool,
+    /// Set by the number keys or the scroll wheel and cleared once a fixed
+    /// update has used it.
+    pub weapon_select: Option<WeaponSelect>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WeaponSelect {
+    /// Zero-based position in the arsenal.
+    Slot(usize),
+    Next,
+    Previous,
+}
+
+const WEAPON_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
 fn pause(keyboard_input: Res<Input<KeyCode>>, mut next_state: ResMut<NextState<AppState>>) {
     if keyboard_input.just_pressed(KeyCode::Escape) {
         next_state.set(AppState::Paused);
     }
 }
+
+fn read_player_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut cursor_moved_events: EventReader<CursorMoved>,
+    mut mouse_button_input_events: EventReader<MouseButtonInput>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    mut player_input: ResMut<PlayerInput>,
+    settings: Res<LaunchSettings>,
+) {
+    let mut movement = Vec2::ZERO;
+
+    if keyboard_input.pressed(KeyCode::W) {
+        movement.y = 1.0;
+    }
+
+    if keyboard_input.pressed(KeyCode::A) {
+        movement.x = -1.0;
+    }
+
+    if keyboard_input.pressed(KeyCode::S) {
+        movement.y = -1.0;
+    }
+
+    if keyboard_input.pressed(KeyCode::D) {
+        movement.x = 1.0;
+    }
+
+    player_input.movement = movement;
+    player_input.reload = keyboard_input.pressed(KeyCode::R);
+
+    if let Some(event) = cursor_moved_events.iter().next() {
+        if let Ok((camera, camera_transform)) = camera_q.get_single() {
+            if let Some(position) = camera
+                .viewport_to_world(camera_transform, event.position)
+                .map(|ray| ray.origin.truncate())
+            {
+                player_input.aim = Some(position);
+            }
+        }
+    }
+
+    if let Some(click_event) = mouse_button_input_events.iter().next() {
+        if click_event.state == ButtonState::Pressed {
+            player_input.fire = true;
+        }
+    }
+
+    player_input.trigger = mouse_button_input.pressed(MouseButton::Left);
+    if settings.hold_to_fire && player_input.trigger {
+        // Every weapon then fires as fast as its cooldown allows.
+        player_input.fire = true;
+    }
+}
+
+fn read_weapon_select(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut player_input: ResMut<PlayerInput>,
+) {
+    for event in mouse_wheel_events.iter() {
+        if event.y < 0.0 {
+            player_input.weapon_select = Some(WeaponSelect::Next);
+        } else if event.y > 0.0 {
+            player_input.weapon_select = Some(WeaponSelect::Previous);
+        }
+    }
+
+    for (slot, key) in WEAPON_KEYS.iter().enumerate() {
+        if keyboard_input.just_pressed(*key) {
+            player_input.weapon_select = Some(WeaponSelect::Slot(slot));
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::states::AppState;
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_rapier2d::prelude::*;
+
+use super::FixedSet;
+
+/// Smooths rigid bodies between fixed updates. Each fixed update records where
+/// bodies ended up, and every frame their `Transform` is blended between the
+/// last two positions before being drawn. The blended value is put back to
+/// the simulated one before the next fixed update runs, so only rendering ever
+/// sees it.
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(track_new_bodies.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(
+            restore_transforms
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .in_base_set(FixedSet::Input),
+        );
+        app.add_system(
+            snapshot_transforms
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .in_base_set(FixedSet::PostPhysics),
+        );
+        app.add_system(
+            interpolate_transforms
+                .in_base_set(CoreSet::PostUpdate)
+                .before(TransformSystem::TransformPropagate)
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+#[derive(Component)]
+struct InterpolatedTransform {
+    previous: Transform,
+    current: Transform,
+}
+
+/// Fixed bodies never move, so they are left alone.
+fn track_new_bodies(
+    mut commands: Commands,
+    body_query: Query<(Entity, &Transform, &RigidBody), Added<RigidBody>>,
+) {
+    for (entity, transform, body) in body_query.iter() {
+        if *body == RigidBody::Fixed {
+            continue;
+        }
+        commands.entity(entity).insert(InterpolatedTransform {
+            previous: *transform,
+            current: *transform,
+        });
+    }
+}
+
+fn restore_transforms(mut query: Query<(&mut Transform, &InterpolatedTransform)>) {
+    for (mut transform, interpolated) in query.iter_mut() {
+        if *transform != interpolated.current {
+            *transform = interpolated.current;
+        }
+    }
+}
+
+fn snapshot_transforms(mut query: Query<(&Transform, &mut InterpolatedTransform)>) {
+    for (transform, mut interpolated) in query.iter_mut() {
+        interpolated.previous = interpolated.current;
+        interpolated.current = *transform;
+    }
+}
+
+fn interpolate_transforms(
+    fixed_time: Res<FixedTime>,
+    mut query: Query<(&mut Transform, &InterpolatedTransform)>,
+) {
+    let alpha =
+        (fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32()).clamp(0.0, 1.0);
+
+    for (mut transform, interpolated) in query.iter_mut() {
+        let blended = Transform {
+            translation: interpolated
+                .previous
+                .translation
+                .lerp(interpolated.current.translation, alpha),
+            rotation: interpolated
+                .previous
+                .rotation
+                .slerp(interpolated.current.rotation, alpha),
+            ..*transform
+        };
+        // Bodies at rest keep their change detection quiet.
+        transform.set_if_neq(blended);
+    }
+}
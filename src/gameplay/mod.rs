@@ -1,32 +1,144 @@
 use bevy::prelude::*;
 use interact::InteractPlugin;
 
+use self::audio::GameAudioPlugin;
+use self::awareness::AwarenessPlugin;
+use self::boss::BossPlugin;
+use self::clock::ClockPlugin;
+use self::enemies::EnemySpritePlugin;
+use self::events::GameplayEventsPlugin;
+use self::interpolation::InterpolationPlugin;
+use self::pathfinding::PathfindingPlugin;
+use self::pickups::{PickupPlugin, PickupSpritePlugin};
+use self::player::PlayerSpritePlugin;
+use self::projectiles::{ProjectilePlugin, ProjectileSpritePlugin};
+use self::spatial::SpatialPlugin;
+use self::stats::StatsPlugin;
 use self::ui::UIPlugin;
 use self::{
-    enemies::EnemyPlugin, physics::PhysicsPlugin, player::PlayerPlugin, world::WorldPlugin,
+    enemies::EnemyPlugin,
+    physics::PhysicsPlugin,
+    player::PlayerPlugin,
+    world::{WorldPlugin, WorldSpritePlugin},
 };
 
+mod audio;
+mod awareness;
+mod bestiary;
+mod boss;
+mod clock;
+mod data;
 mod enemies;
 mod events;
 mod interact;
+mod interpolation;
+mod pathfinding;
 mod physics;
+mod pickups;
 mod player;
+mod projectiles;
+mod spatial;
+mod stages;
+mod stats;
+mod steering;
 mod ui;
+mod weapon;
 mod world;
 
+/// Ordering of the simulation inside `CoreSchedule::FixedUpdate`, around
+/// rapier's `PhysicsSet`s.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[system_set(base)]
+pub enum FixedSet {
+    /// Before anything reads `PlayerInput`.
+    Input,
+    /// Player and enemy logic, before the physics step.
+    Update,
+    /// Applies the commands from `Update` so rapier sees new entities.
+    UpdateFlush,
+    /// After the physics step, when its collision events can be read.
+    PostPhysics,
+    /// Score, stats and anything else reacting to the step's gameplay
+    /// events, once every system that sends them has run.
+    Reactions,
+}
+
+/// The systems in `FixedSet::Update` that draw from `GlobalRng`, in the order
+/// they draw. Any other order would give a seed and its replay different
+/// numbers from run to run.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RngSet {
+    EnemyStates,
+    Boss,
+    Spawns,
+    StageReward,
+    Player,
+}
+
+/// The simulation: player, enemies, physics and the arena. Needs no window,
+/// renderer or audio device, so it can run under `MinimalPlugins`.
 pub struct GameplayPlugin;
 
 impl Plugin for GameplayPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(InteractPlugin);
+        app.init_resource::<PlayerInput>();
+        app.add_plugin(GameplayEventsPlugin);
+        app.add_plugin(ClockPlugin);
         app.add_plugin(PlayerPlugin);
         app.add_plugin(WorldPlugin);
         app.add_plugin(PhysicsPlugin);
+        app.add_plugin(PathfindingPlugin);
+        app.add_plugin(SpatialPlugin);
         app.add_plugin(EnemyPlugin);
+        app.add_plugin(AwarenessPlugin);
+        app.add_plugin(BossPlugin);
+        app.add_plugin(ProjectilePlugin);
+        app.add_plugin(PickupPlugin);
+        app.add_plugin(StatsPlugin);
+        app.edit_schedule(CoreSchedule::FixedUpdate, |schedule| {
+            schedule.configure_sets(
+                (
+                    RngSet::EnemyStates,
+                    RngSet::Boss,
+                    RngSet::Spawns,
+                    RngSet::StageReward,
+                    RngSet::Player,
+                )
+                    .chain(),
+            );
+        });
+    }
+}
+
+/// Everything the simulation doesn't need: device input, sprites, sounds and
+/// the HUD. Add alongside `GameplayPlugin` when there is a window.
+pub struct GameplayPresentationPlugin;
+
+impl Plugin for GameplayPresentationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InteractPlugin);
+        app.add_plugin(PlayerSpritePlugin);
+        app.add_plugin(EnemySpritePlugin);
+        app.add_plugin(PickupSpritePlugin);
+        app.add_plugin(ProjectileSpritePlugin);
+        app.add_plugin(WorldSpritePlugin);
+        app.add_plugin(InterpolationPlugin);
+        app.add_plugin(GameAudioPlugin);
         app.add_plugin(UIPlugin);
     }
 }
 
-pub use enemies::{Enemy, EnemySpawner};
+pub use bestiary::Bestiary;
+pub use clock::GameClock;
+pub use data::{DataAsset, DataAssetLoader};
+pub use enemies::{Enemy, EnemySpawner, SpawnTelegraph};
+pub use events::{PlayerHit, StageCleared, StageStarted};
+pub use interact::{PlayerInput, WeaponSelect};
+pub use pickups::{DropTables, Pickup};
 pub use player::Bullet;
 pub use player::Player;
+pub use projectiles::Spit;
+pub use spatial::EnemyGrid;
+pub use stages::Stages;
+pub use stats::RunStats;
+pub use weapon::Arsenal;
@@ -0,0 +1,243 @@
+use crate::states::AppState;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy::utils::HashSet;
+use bevy_rapier2d::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use super::player::Player;
+use super::FixedSet;
+
+pub struct PathfindingPlugin;
+
+impl Plugin for PathfindingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FlowField>();
+        app.add_system(
+            update_flow_field
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .in_base_set(FixedSet::Update)
+                .run_if(in_state(AppState::InGame)),
+        );
+        app.add_system(
+            track_obstacles
+                .in_base_set(CoreSet::PostUpdate)
+                .after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Side of a grid cell.
+const CELL_SIZE: f32 = 20.0;
+/// The grid covers the inside of the arena walls.
+const GRID_HALF_SIZE: Vec2 = Vec2::new(390.0, 290.0);
+/// Cells this close to an obstacle count as blocked, so enemies don't catch
+/// on corners.
+const CLEARANCE: f32 = 10.0;
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+/// Grid over the arena holding each cell's path cost to the player's cell.
+/// Enemies head for the cheapest neighbouring cell, which leads around walls
+/// and furniture.
+#[derive(Resource)]
+pub struct FlowField {
+    width: usize,
+    height: usize,
+    blocked: Vec<bool>,
+    /// `u32::MAX` for cells the player can't be reached from.
+    cost: Vec<u32>,
+    /// Cell the costs lead to.
+    target: Option<(usize, usize)>,
+    /// Fixed bodies, so removing one is noticed.
+    obstacles: HashSet<Entity>,
+    /// A fixed body appeared or went, and `blocked` needs redoing.
+    obstacles_changed: bool,
+}
+
+impl Default for FlowField {
+    fn default() -> Self {
+        let width = (GRID_HALF_SIZE.x * 2.0 / CELL_SIZE).ceil() as usize;
+        let height = (GRID_HALF_SIZE.y * 2.0 / CELL_SIZE).ceil() as usize;
+        Self {
+            width,
+            height,
+            blocked: vec![false; width * height],
+            cost: vec![u32::MAX; width * height],
+            target: None,
+            obstacles: HashSet::new(),
+            obstacles_changed: false,
+        }
+    }
+}
+
+impl FlowField {
+    fn cell(&self, position: Vec2) -> (usize, usize) {
+        let local = ((position + GRID_HALF_SIZE) / CELL_SIZE).floor();
+        (
+            (local.x.max(0.0) as usize).min(self.width - 1),
+            (local.y.max(0.0) as usize).min(self.height - 1),
+        )
+    }
+
+    fn center(&self, (x, y): (usize, usize)) -> Vec2 {
+        Vec2::new(x as f32 + 0.5, y as f32 + 0.5) * CELL_SIZE - GRID_HALF_SIZE
+    }
+
+    fn index(&self, (x, y): (usize, usize)) -> usize {
+        y * self.width + x
+    }
+
+    /// Open cells around `cell` with the cost of moving there. Diagonals
+    /// need both cells beside them open, so paths don't cut corners.
+    fn neighbours(
+        &self,
+        (x, y): (usize, usize),
+    ) -> impl Iterator<Item = ((usize, usize), u32)> + '_ {
+        let open = move |dx: i32, dy: i32| {
+            let nx = x as i32 + dx;
+            let ny = y as i32 + dy;
+            if nx < 0 || ny < 0 || nx >= self.width as i32 || ny >= self.height as i32 {
+                return None;
+            }
+            let cell = (nx as usize, ny as usize);
+            (!self.blocked[self.index(cell)]).then_some(cell)
+        };
+
+        [
+            (-1, 0),
+            (1, 0),
+            (0, -1),
+            (0, 1),
+            (-1, -1),
+            (-1, 1),
+            (1, -1),
+            (1, 1),
+        ]
+        .into_iter()
+        .filter_map(move |(dx, dy)| {
+            let cell = open(dx, dy)?;
+            if dx != 0 && dy != 0 {
+                open(dx, 0)?;
+                open(0, dy)?;
+                Some((cell, DIAGONAL_COST))
+            } else {
+                Some((cell, STRAIGHT_COST))
+            }
+        })
+    }
+
+    /// Dijkstra out from `target` over the open cells.
+    fn recompute(&mut self, target: (usize, usize)) {
+        self.target = Some(target);
+        self.cost.fill(u32::MAX);
+
+        let mut frontier = BinaryHeap::new();
+        let start = self.index(target);
+        self.cost[start] = 0;
+        frontier.push(Reverse((0, target)));
+
+        while let Some(Reverse((cost, cell))) = frontier.pop() {
+            if cost > self.cost[self.index(cell)] {
+                continue;
+            }
+            let neighbours: Vec<_> = self.neighbours(cell).collect();
+            for (next, step) in neighbours {
+                let next_cost = cost + step;
+                let index = self.index(next);
+                if next_cost < self.cost[index] {
+                    self.cost[index] = next_cost;
+                    frontier.push(Reverse((next_cost, next)));
+                }
+            }
+        }
+    }
+
+    /// Which way to go from `position` to reach the player, or `None` when
+    /// already in the player's cell or cut off from it.
+    pub fn direction(&self, position: Vec2) -> Option<Vec2> {
+        let cell = self.cell(position);
+        if Some(cell) == self.target {
+            return None;
+        }
+
+        let here = self.cost[self.index(cell)];
+        let (best, cost) = self
+            .neighbours(cell)
+            .map(|(next, _)| (next, self.cost[self.index(next)]))
+            .min_by_key(|(_, cost)| *cost)?;
+        if cost == u32::MAX || (here != u32::MAX && cost >= here) {
+            return None;
+        }
+        Some((self.center(best) - position).normalize_or_zero())
+    }
+}
+
+/// Flags the obstacles for redoing when a fixed body appears or goes. Runs
+/// after transforms propagate, so a new body is in place by the next fixed
+/// update.
+fn track_obstacles(
+    mut flow_field: ResMut<FlowField>,
+    added_body_query: Query<(Entity, &RigidBody), Added<RigidBody>>,
+    mut removed_bodies: RemovedComponents<RigidBody>,
+) {
+    for (entity, body) in added_body_query.iter() {
+        if *body == RigidBody::Fixed {
+            flow_field.obstacles.insert(entity);
+            flow_field.obstacles_changed = true;
+        }
+    }
+    for entity in removed_bodies.iter() {
+        if flow_field.obstacles.contains(&entity) {
+            flow_field.obstacles.remove(&entity);
+            flow_field.obstacles_changed = true;
+        }
+    }
+}
+
+/// Marks the cells fixed colliders cover when they appear or go, and
+/// recomputes the costs whenever that changes or the player moves to another
+/// cell.
+pub fn update_flow_field(
+    mut flow_field: ResMut<FlowField>,
+    obstacle_query: Query<(&RigidBody, &Collider, &GlobalTransform)>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let obstacles_changed = flow_field.obstacles_changed;
+    if obstacles_changed {
+        flow_field.obstacles_changed = false;
+        let obstacles: Vec<_> = obstacle_query
+            .iter()
+            .filter(|(body, _, _)| **body == RigidBody::Fixed)
+            .map(|(_, collider, transform)| {
+                let (_, rotation, translation) = transform.to_scale_rotation_translation();
+                (
+                    collider,
+                    translation.truncate(),
+                    rotation.to_euler(EulerRot::XYZ).2,
+                )
+            })
+            .collect();
+
+        for y in 0..flow_field.height {
+            for x in 0..flow_field.width {
+                let center = flow_field.center((x, y));
+                let index = flow_field.index((x, y));
+                flow_field.blocked[index] =
+                    obstacles.iter().any(|(collider, translation, rotation)| {
+                        collider.distance_to_point(*translation, *rotation, center, true)
+                            <= CLEARANCE
+                    });
+            }
+        }
+    }
+
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let target = flow_field.cell(player_transform.translation.truncate());
+    if obstacles_changed || flow_field.target != Some(target) {
+        flow_field.recompute(target);
+    }
+}
@@ -2,12 +2,31 @@ use crate::states::AppState;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
+use super::clock::GameClock;
+use super::FixedSet;
+
+/// Bullets, which pass through each other.
+pub const BULLET_GROUP: Group = Group::GROUP_1;
+/// Pickups, which only the player touches.
+pub const PICKUP_GROUP: Group = Group::GROUP_2;
+/// Only the player is in this group, so things like pickups can filter for it.
+pub const PLAYER_GROUP: Group = Group::GROUP_3;
+/// Enemy projectiles, which pass through enemies and each other.
+pub const SPIT_GROUP: Group = Group::GROUP_4;
+
 pub struct PhysicsPlugin;
 
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
+        // One rapier step per fixed update, so the simulation doesn't depend
+        // on the render frame rate.
+        let step = app.world.resource::<GameClock>().step();
         app.insert_resource(RapierConfiguration {
             gravity: Vec2::ZERO,
+            timestep_mode: TimestepMode::Fixed {
+                dt: step.as_secs_f32(),
+                substeps: 1,
+            },
             ..default()
         });
 
@@ -25,37 +44,54 @@ impl Plugin for PhysicsPlugin {
                 .with_default_system_setup(false),
         );
 
-        app.configure_sets(
-            (
-                PhysicsSet::SyncBackend,
-                PhysicsSet::SyncBackendFlush,
-                PhysicsSet::StepSimulation,
-                PhysicsSet::Writeback,
-            )
-                .chain()
-                .after(CoreSet::UpdateFlush)
-                .before(CoreSet::PostUpdate),
-        );
+        app.edit_schedule(CoreSchedule::FixedUpdate, |schedule| {
+            schedule.configure_sets(
+                (
+                    FixedSet::Input,
+                    FixedSet::Update,
+                    FixedSet::UpdateFlush,
+                    PhysicsSet::SyncBackend,
+                    PhysicsSet::SyncBackendFlush,
+                    PhysicsSet::StepSimulation,
+                    PhysicsSet::Writeback,
+                    FixedSet::PostPhysics,
+                    FixedSet::Reactions,
+                )
+                    .chain(),
+            );
+            schedule.add_system(apply_system_buffers.in_base_set(FixedSet::UpdateFlush));
+        });
 
         app.add_systems(
             sync_backend_system_set
                 //.distributive_run_if(in_state(AppState::InGame))
-                .in_base_set(PhysicsSet::SyncBackend),
+                .in_base_set(PhysicsSet::SyncBackend)
+                .in_schedule(CoreSchedule::FixedUpdate),
         );
         app.add_systems(
             sync_backend_flush_system_set
                 .distributive_run_if(in_state(AppState::InGame))
-                .in_base_set(PhysicsSet::SyncBackendFlush),
+                .in_base_set(PhysicsSet::SyncBackendFlush)
+                .in_schedule(CoreSchedule::FixedUpdate),
         );
         app.add_systems(
             step_simulation_system_set
                 .distributive_run_if(in_state(AppState::InGame))
-                .in_base_set(PhysicsSet::StepSimulation),
+                .in_base_set(PhysicsSet::StepSimulation)
+                .in_schedule(CoreSchedule::FixedUpdate),
         );
         app.add_systems(
             writeback_system_set
                 .distributive_run_if(in_state(AppState::InGame))
-                .in_base_set(PhysicsSet::Writeback),
+                .in_base_set(PhysicsSet::Writeback)
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
+
+        // Removal events only live for two frames, and at high frame rates a
+        // fixed update may not run in that window, so also sync removals
+        // every frame.
+        app.add_system(
+            bevy_rapier2d::plugin::systems::sync_removals.in_base_set(CoreSet::PostUpdate),
         );
 
         //app.add_plugin(RapierDebugRenderPlugin::default());
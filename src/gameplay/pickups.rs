@@ -0,0 +1,230 @@
+use crate::states::AppState;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::Duration;
+use bevy_rapier2d::prelude::*;
+use bevy_turborand::prelude::*;
+use serde::Deserialize;
+
+use super::clock::GameClock;
+use super::data::DataAsset;
+use super::events::PickupCollected;
+use super::physics::{PICKUP_GROUP, PLAYER_GROUP};
+use super::player::Player;
+use super::weapon::Arsenal;
+use super::FixedSet;
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            expire_pickups
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .in_base_set(FixedSet::Update)
+                .run_if(in_state(AppState::InGame)),
+        );
+        app.add_system(
+            collect_pickups
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .in_base_set(FixedSet::PostPhysics)
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// Sprites for pickups.
+pub struct PickupSpritePlugin;
+
+impl Plugin for PickupSpritePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(attach_pickup_sprite.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+/// What drops and how long it stays.
+#[derive(Resource, Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "3f6e2a71-0c4b-4d8e-a5b9-7e1d6c2f8b04"]
+pub struct DropTables {
+    /// Seconds a pickup lies around before disappearing.
+    pub lifetime_secs: f32,
+    /// Rolled once for every killed enemy.
+    pub enemy: DropTable,
+    /// Rolled once when a stage is cleared, dropping somewhere in the arena.
+    pub stage_clear: DropTable,
+}
+
+impl DataAsset for DropTables {
+    const PATH: &'static str = "pickups/drops.pickups.ron";
+
+    fn validate(&self) -> Result<(), String> {
+        if self
+            .enemy
+            .iter()
+            .chain(self.stage_clear.iter())
+            .any(|drop| drop.weight == 0)
+        {
+            return Err("drop weights have to be above 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl DropTables {
+    pub fn lifetime(&self) -> Duration {
+        Duration::from_secs_f32(self.lifetime_secs.max(0.0))
+    }
+}
+
+/// Each drop's chance is its weight over the table's total weight.
+pub type DropTable = Vec<DropEntry>;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DropEntry {
+    pub weight: u32,
+    /// `None` drops nothing.
+    pub item: Option<PickupKind>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum PickupKind {
+    /// Spare rounds for the named weapon.
+    Ammo { weapon: String, rounds: i32 },
+    /// Gives back health, up to the player's maximum.
+    Heal { item: HealItem, amount: i32 },
+}
+
+/// What a `PickupKind::Heal` looks like.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum HealItem {
+    Formula,
+    FirstAid,
+}
+
+pub fn roll_drop(table: &DropTable, rng: &mut GlobalRng) -> Option<PickupKind> {
+    let total: u32 = table.iter().map(|drop| drop.weight).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut roll = rng.u32(0..total);
+    for drop in table {
+        if roll < drop.weight {
+            return drop.item.clone();
+        }
+        roll -= drop.weight;
+    }
+    None
+}
+
+#[derive(Component)]
+pub struct Pickup {
+    pub kind: PickupKind,
+    /// Game time at which it disappears if nobody took it.
+    expires: Duration,
+}
+
+pub fn spawn_pickup(commands: &mut Commands, kind: PickupKind, position: Vec2, expires: Duration) {
+    commands
+        .spawn(Collider::ball(8.0))
+        .insert(Sensor)
+        .insert(CollisionGroups::new(PICKUP_GROUP, PLAYER_GROUP))
+        .insert(TransformBundle::from(Transform::from_xyz(
+            position.x, position.y, 1.5,
+        )))
+        .insert(Pickup { kind, expires });
+}
+
+fn expire_pickups(
+    mut commands: Commands,
+    pickup_query: Query<(&Pickup, Entity)>,
+    clock: Res<GameClock>,
+) {
+    for (pickup, entity) in pickup_query.iter() {
+        if clock.elapsed() >= pickup.expires {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Pickups are sensors, so this checks for overlap every step rather than
+/// waiting for a collision event. A pickup the player can't use yet stays
+/// where it is.
+fn collect_pickups(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    pickup_query: Query<(&Pickup, Entity)>,
+    mut player_query: Query<(&mut Player, Entity)>,
+    arsenal: Res<Arsenal>,
+    mut pickup_events: EventWriter<PickupCollected>,
+) {
+    let Ok((mut player, player_entity)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    for (pickup, entity) in pickup_query.iter() {
+        if rapier_context.intersection_pair(entity, player_entity) != Some(true) {
+            continue;
+        }
+
+        let used = match &pickup.kind {
+            PickupKind::Ammo { weapon, rounds } => {
+                match arsenal.weapons.iter().position(|w| &w.name == weapon) {
+                    Some(index) => {
+                        let max_reserve = arsenal.weapons[index].max_reserve;
+                        let reserve = &mut player.reserve[index];
+                        let before = *reserve;
+                        *reserve = (*reserve + rounds).min(max_reserve);
+                        *reserve > before
+                    }
+                    None => {
+                        warn!("dropped ammo for unknown weapon {weapon}");
+                        true
+                    }
+                }
+            }
+            PickupKind::Heal { amount, .. } => {
+                let before = player.health;
+                player.health = (player.health + amount).min(player.max_health);
+                player.health > before
+            }
+        };
+
+        if used {
+            debug!("picked up {:?}", pickup.kind);
+            pickup_events.send(PickupCollected {
+                kind: pickup.kind.clone(),
+            });
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn attach_pickup_sprite(
+    mut commands: Commands,
+    pickup_query: Query<(Entity, &Pickup), Added<Pickup>>,
+) {
+    for (entity, pickup) in pickup_query.iter() {
+        let (color, size) = match pickup.kind {
+            PickupKind::Ammo { .. } => (Color::rgb(0.6, 0.1, 0.1), Vec2::new(12.0, 8.0)),
+            PickupKind::Heal {
+                item: HealItem::Formula,
+                ..
+            } => (Color::rgb(0.95, 0.95, 0.85), Vec2::new(6.0, 12.0)),
+            PickupKind::Heal {
+                item: HealItem::FirstAid,
+                ..
+            } => (Color::rgb(0.1, 0.6, 0.1), Vec2::new(12.0, 12.0)),
+        };
+
+        commands.entity(entity).insert((
+            Sprite {
+                color,
+                custom_size: Some(size),
+                ..default()
+            },
+            Handle::<Image>::default(),
+            VisibilityBundle::default(),
+        ));
+    }
+}
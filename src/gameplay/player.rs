@@ -1,41 +1,89 @@
-use crate::{loading::AudioHandles, states::AppState};
-use bevy::{
-    input::{mouse::MouseButtonInput, ButtonState},
-    prelude::*,
-};
+use crate::states::AppState;
+use bevy::prelude::*;
+use bevy::utils::Duration;
 use bevy_rapier2d::prelude::*;
 use bevy_turborand::{DelegatedRng, GlobalRng};
-use instant::{Duration, Instant};
+use serde::Deserialize;
+
+use super::clock::GameClock;
+use super::events::{PlayerHit, ReloadStarted, ShotFired};
+use super::interact::{PlayerInput, WeaponSelect};
+use super::physics::{BULLET_GROUP, PICKUP_GROUP};
+use super::weapon::{Arsenal, Weapon};
+use super::{FixedSet, RngSet};
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
+        app.add_event::<PlaySound>();
         app.add_system(spawn_player.in_schedule(OnEnter(AppState::InGame)));
-        app.add_system(pause.in_set(OnUpdate(AppState::InGame)));
-        app.add_system(rotate_player.in_set(OnUpdate(AppState::InGame)));
+        app.add_systems(
+            (
+                rotate_player,
+                switch_weapon,
+                reload_weapon.after(switch_weapon),
+                move_player.after(reload_weapon),
+                player_click.after(move_player).in_set(RngSet::Player),
+                consume_player_input
+                    .after(rotate_player)
+                    .after(switch_weapon)
+                    .after(player_click),
+                despawn_collided_bullets,
+                detect_player_death,
+            )
+                .in_base_set(FixedSet::Update)
+                .distributive_run_if(in_state(AppState::InGame))
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
         app.add_system(
-            player_click
-                .after(move_player)
-                .in_set(OnUpdate(AppState::InGame)),
+            take_player_hits
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .in_base_set(FixedSet::Reactions)
+                .run_if(in_state(AppState::InGame)),
         );
-        app.add_system(move_player.in_set(OnUpdate(AppState::InGame)));
-        app.add_system(despawn_collided_bullets.in_set(OnUpdate(AppState::InGame)));
-        app.add_system(animate_player.in_set(OnUpdate(AppState::InGame)));
         app.add_system(process_sounds.in_set(OnUpdate(AppState::InGame)));
-        app.add_system(detect_player_death.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+/// Sprites and animation for the player and their bullets.
+pub struct PlayerSpritePlugin;
+
+impl Plugin for PlayerSpritePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(attach_player_sprite.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(attach_bullet_sprite.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(animate_player.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(flash_invulnerable_player.in_set(OnUpdate(AppState::InGame)));
     }
 }
 
 #[derive(Component)]
 pub struct Player {
     pub health: i32,
-    pub shells: i32,
+    /// Healing stops here.
+    pub max_health: i32,
+    /// Game time until which enemies can't bite.
+    pub invulnerable_until: Duration,
+    /// Index of the weapon in hand in the `Arsenal`.
+    pub weapon: usize,
+    /// Rounds in each weapon's magazine, by `Arsenal` index.
+    pub ammo: Vec<i32>,
+    /// Spare rounds for each weapon, which reloading takes from.
+    pub reserve: Vec<i32>,
+    /// Game time the current reload step started, while reloading.
+    pub reload_step_started: Option<Duration>,
+    /// Game time each weapon can fire again, by `Arsenal` index.
+    pub next_shot: Vec<Duration>,
+    /// A click that came in shortly before `next_shot`, fired once it's due.
+    pub shot_buffered: bool,
 }
 
 #[derive(Component, Default)]
 pub struct Bullet {
     pub collided: bool,
+    /// Health taken off the enemy it hits.
+    pub damage: i32,
 }
 
 #[derive(Component)]
@@ -46,39 +94,33 @@ struct AnimationIndices {
 
 #[derive(Component)]
 pub struct PlayerSound {
-    pub time_to_play: Instant,
+    /// Game time at which to play the sound.
+    pub time_to_play: Duration,
     pub sound_type: PlayerSoundType,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy, Debug, Deserialize)]
 pub enum PlayerSoundType {
     Cock,
     Gunshot,
     Shell,
     Bite,
     Empty,
+    Hit,
 }
 
+/// Sent when a sound is due. Played by `GameAudioPlugin` when there is an
+/// audio device, otherwise dropped.
+pub struct PlaySound(pub PlayerSoundType);
+
 #[derive(Component, Deref, DerefMut)]
 struct AnimationTimer(Timer);
 
-fn spawn_player(
-    mut commands: Commands,
-    player_query: Query<&Player>,
-    asset_server: Res<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
-) {
-    for _ in player_query.iter() {
+fn spawn_player(mut commands: Commands, player_query: Query<&Player>, arsenal: Res<Arsenal>) {
+    if !player_query.is_empty() {
         return;
     }
 
-    let texture_handle = asset_server.load("sprites/hotwife.png");
-    let texture_atlas =
-        TextureAtlas::from_grid(texture_handle, Vec2::new(64.0, 64.0), 1, 6, None, None);
-    let texture_atlas_handle = texture_atlases.add(texture_atlas);
-    // Use only the subset of sprites in the sheet that make up the run animation
-    let animation_indices = AnimationIndices { first: 1, last: 5 };
-
     commands
         .spawn(RigidBody::Dynamic)
         .insert(Sleeping::disabled())
@@ -96,23 +138,68 @@ fn spawn_player(
         })
         .insert(Player {
             health: 100,
-            shells: 6,
+            max_health: 100,
+            invulnerable_until: Duration::ZERO,
+            weapon: 0,
+            ammo: arsenal
+                .weapons
+                .iter()
+                .map(|weapon| weapon.magazine)
+                .collect(),
+            reserve: arsenal
+                .weapons
+                .iter()
+                .map(|weapon| weapon.reserve)
+                .collect(),
+            reload_step_started: None,
+            next_shot: vec![Duration::ZERO; arsenal.weapons.len()],
+            shot_buffered: false,
         })
-        .insert((
-            SpriteSheetBundle {
-                texture_atlas: texture_atlas_handle,
-                sprite: TextureAtlasSprite::new(animation_indices.first),
-                ..default()
-            },
-            animation_indices,
-            AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-        ))
         .insert(TransformBundle::from(Transform::from_xyz(0.0, 0.0, 2.0)));
     info!("spawned player")
 }
 
+fn attach_player_sprite(
+    mut commands: Commands,
+    player_query: Query<Entity, Added<Player>>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    for entity in player_query.iter() {
+        let texture_handle = asset_server.load("sprites/hotwife.png");
+        let texture_atlas =
+            TextureAtlas::from_grid(texture_handle, Vec2::new(64.0, 64.0), 1, 6, None, None);
+        let texture_atlas_handle = texture_atlases.add(texture_atlas);
+        // Use only the subset of sprites in the sheet that make up the run animation
+        let animation_indices = AnimationIndices { first: 1, last: 5 };
+
+        commands.entity(entity).insert((
+            texture_atlas_handle,
+            TextureAtlasSprite::new(animation_indices.first),
+            VisibilityBundle::default(),
+            animation_indices,
+            AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
+        ));
+    }
+}
+
+fn attach_bullet_sprite(mut commands: Commands, bullet_query: Query<Entity, Added<Bullet>>) {
+    for entity in bullet_query.iter() {
+        commands.entity(entity).insert((
+            Sprite {
+                color: Color::BLACK,
+                custom_size: Some(Vec2::new(5.0, 5.0)),
+                ..default()
+            },
+            Handle::<Image>::default(),
+            VisibilityBundle::default(),
+        ));
+    }
+}
+
 fn animate_player(
     time: Res<Time>,
+    clock: Res<GameClock>,
     mut query: Query<(
         &AnimationIndices,
         &Player,
@@ -122,7 +209,7 @@ fn animate_player(
     )>,
 ) {
     for (indices, _, force, mut timer, mut sprite) in &mut query {
-        timer.tick(time.delta());
+        timer.tick(time.delta().mul_f32(clock.scale));
         if timer.just_finished() {
             if (force.force.x > 0.1 || force.force.x < -0.1)
                 || (force.force.y > 0.1 || force.force.y < -0.1)
@@ -139,137 +226,219 @@ fn animate_player(
     }
 }
 
-fn pause(keyboard_input: Res<Input<KeyCode>>, mut next_state: ResMut<NextState<AppState>>) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
-        next_state.set(AppState::Paused);
+/// Blinks the player while bites can't land.
+fn flash_invulnerable_player(
+    clock: Res<GameClock>,
+    mut query: Query<(&Player, &mut TextureAtlasSprite)>,
+) {
+    for (player, mut sprite) in &mut query {
+        let remaining = player.invulnerable_until.saturating_sub(clock.elapsed());
+        let alpha = if !remaining.is_zero() && (remaining.as_millis() / 100) % 2 == 0 {
+            0.3
+        } else {
+            1.0
+        };
+        if sprite.color.a() != alpha {
+            sprite.color.set_a(alpha);
+        }
     }
 }
 
 fn rotate_player(
-    mut cursor_moved_events: EventReader<CursorMoved>,
-    mut player_query: Query<(&Player, &mut Transform)>,
-    camera_q: Query<(&Camera, &GlobalTransform)>,
+    player_input: Res<PlayerInput>,
+    mut player_query: Query<&mut Transform, With<Player>>,
 ) {
-    let (camera, camera_transform) = camera_q.single();
-
-    if let Some(event) = cursor_moved_events.iter().next() {
-        for (_, mut t) in player_query.iter_mut() {
-            if let Some(position) = camera
-                .viewport_to_world(camera_transform, event.position)
-                .map(|ray| ray.origin.truncate())
-            {
-                let mut rads = (t.translation.y - position.y).atan2(t.translation.x - position.x);
-                rads = rads + (std::f32::consts::PI / 2.);
-                t.rotation = Quat::from_rotation_z(rads);
-                // info!("{:?}", position)
-            }
+    if let Some(position) = player_input.aim {
+        for mut t in player_query.iter_mut() {
+            let mut rads = (t.translation.y - position.y).atan2(t.translation.x - position.x);
+            rads += std::f32::consts::PI / 2.;
+            t.rotation = Quat::from_rotation_z(rads);
         }
     }
 }
 
-fn move_player(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut player_query: Query<(&mut ExternalForce, &mut Player)>,
+fn switch_weapon(
+    player_input: Res<PlayerInput>,
+    mut player_query: Query<&mut Player>,
+    arsenal: Res<Arsenal>,
 ) {
-    for (mut external_force, mut player) in player_query.iter_mut() {
-        external_force.force.x = 0.0;
-        external_force.force.y = 0.0;
-
-        if keyboard_input.pressed(KeyCode::W) {
-            external_force.force.y = 15.0;
+    let Some(weapon_select) = player_input.weapon_select else {
+        return;
+    };
+
+    let count = arsenal.weapons.len();
+    for mut player in player_query.iter_mut() {
+        let weapon = match weapon_select {
+            WeaponSelect::Slot(slot) if slot < count => slot,
+            WeaponSelect::Slot(_) => continue,
+            WeaponSelect::Next => (player.weapon + 1) % count,
+            WeaponSelect::Previous => (player.weapon + count - 1) % count,
+        };
+
+        if weapon != player.weapon {
+            player.weapon = weapon;
+            player.reload_step_started = None;
+            player.shot_buffered = false;
+            info!("switched to {}", arsenal.weapons[weapon].name);
         }
+    }
+}
 
-        if keyboard_input.pressed(KeyCode::A) {
-            external_force.force.x = -15.0;
+/// R starts a reload, which then loads a step at a time from the reserve
+/// until the magazine is full or the reserve runs out. Firing or switching
+/// weapons stops it.
+fn reload_weapon(
+    player_input: Res<PlayerInput>,
+    mut player_query: Query<&mut Player>,
+    mut commands: Commands,
+    mut reload_started_events: EventWriter<ReloadStarted>,
+    arsenal: Res<Arsenal>,
+    clock: Res<GameClock>,
+) {
+    let now = clock.elapsed();
+    for mut player in player_query.iter_mut() {
+        let index = player.weapon;
+        let weapon = &arsenal.weapons[index];
+
+        if player_input.reload
+            && player.reload_step_started.is_none()
+            && player.ammo[index] < weapon.magazine
+            && player.reserve[index] > 0
+        {
+            player.reload_step_started = Some(now);
+            reload_started_events.send(ReloadStarted);
         }
 
-        if keyboard_input.pressed(KeyCode::S) {
-            external_force.force.y = -15.0;
-        }
+        while let Some(step_started) = player.reload_step_started {
+            if now < step_started + weapon.reload.step() {
+                break;
+            }
 
-        if keyboard_input.pressed(KeyCode::D) {
-            external_force.force.x = 15.0;
-        }
+            let rounds = weapon
+                .reload
+                .rounds
+                .unwrap_or(weapon.magazine)
+                .min(weapon.magazine - player.ammo[index])
+                .min(player.reserve[index]);
+            if rounds <= 0 {
+                player.reload_step_started = None;
+                break;
+            }
+            player.ammo[index] += rounds;
+            player.reserve[index] -= rounds;
+            for weapon_sound in &weapon.reload.sounds {
+                commands.spawn(PlayerSound {
+                    time_to_play: now + weapon_sound.delay(),
+                    sound_type: weapon_sound.sound,
+                });
+            }
 
-        if keyboard_input.pressed(KeyCode::R) {
-            player.shells = 6;
+            player.reload_step_started =
+                if player.ammo[index] < weapon.magazine && player.reserve[index] > 0 {
+                    Some(step_started + weapon.reload.step())
+                } else {
+                    None
+                };
         }
     }
 }
 
+fn move_player(
+    player_input: Res<PlayerInput>,
+    mut player_query: Query<(&mut ExternalForce, &Player)>,
+    arsenal: Res<Arsenal>,
+) {
+    for (mut external_force, player) in player_query.iter_mut() {
+        let speed = if player.reload_step_started.is_some() {
+            arsenal.weapons[player.weapon].reload.move_speed
+        } else {
+            1.0
+        };
+        external_force.force = player_input.movement * 15.0 * speed;
+    }
+}
+
+/// How early a click can come before the weapon is ready and still fire.
+const SHOT_BUFFER: Duration = Duration::from_millis(150);
+
 fn player_click(
-    mut mouse_button_input_events: EventReader<MouseButtonInput>,
+    player_input: Res<PlayerInput>,
     rng: ResMut<GlobalRng>,
     mut player_query: Query<(&mut Player, &Transform, &mut ExternalForce)>,
     mut commands: Commands,
+    clock: Res<GameClock>,
+    mut shot_fired_events: EventWriter<ShotFired>,
+    arsenal: Res<Arsenal>,
 ) {
-    if let Some((mut player, transform, mut force)) = player_query.iter_mut().next() {
-        if let Some(click_event) = mouse_button_input_events.iter().next() {
-            if click_event.state == ButtonState::Pressed {
-                if player.shells > 0 {
-                    commands.spawn(PlayerSound {
-                        time_to_play: Instant::now(),
-                        sound_type: PlayerSoundType::Gunshot,
-                    });
-
-                    commands.spawn(PlayerSound {
-                        time_to_play: Instant::now() + Duration::from_millis(300),
-                        sound_type: PlayerSoundType::Cock,
-                    });
-
-                    commands.spawn(PlayerSound {
-                        time_to_play: Instant::now() + Duration::from_millis(600),
-                        sound_type: PlayerSoundType::Shell,
-                    });
+    let now = clock.elapsed();
+    if let Some((mut player, transform, force)) = player_query.iter_mut().next() {
+        let index = player.weapon;
+        let weapon = &arsenal.weapons[index];
+
+        if now < player.next_shot[index] {
+            if player_input.fire && player.next_shot[index] - now <= SHOT_BUFFER {
+                player.shot_buffered = true;
+            }
+            return;
+        }
 
-                    spawn_shotgun_blast(commands, transform, rng, force);
+        let held = player_input.trigger && weapon.automatic;
+        if player_input.fire || held || player.shot_buffered {
+            player.shot_buffered = false;
+            if player.ammo[index] > 0 {
+                // A loaded round cuts the reload short.
+                player.reload_step_started = None;
 
-                    player.shells -= 1;
-                } else {
+                for weapon_sound in &weapon.fire_sounds {
                     commands.spawn(PlayerSound {
-                        time_to_play: Instant::now(),
-                        sound_type: PlayerSoundType::Empty,
+                        time_to_play: now + weapon_sound.delay(),
+                        sound_type: weapon_sound.sound,
                     });
                 }
+
+                spawn_blast(commands, weapon, transform, rng, force);
+
+                player.ammo[index] -= 1;
+                player.next_shot[index] = now + weapon.cooldown();
+                shot_fired_events.send(ShotFired {
+                    pellets: weapon.pellets,
+                });
+            } else if player_input.fire && player.reload_step_started.is_none() {
+                player.next_shot[index] = now + weapon.cooldown();
+                commands.spawn(PlayerSound {
+                    time_to_play: now,
+                    sound_type: weapon.empty_sound,
+                });
             }
         }
     }
 }
 
+/// Clicks and cursor moves are kept until a fixed update has seen them, then
+/// cleared here so they only act once.
+fn consume_player_input(mut player_input: ResMut<PlayerInput>) {
+    player_input.fire = false;
+    player_input.aim = None;
+    player_input.weapon_select = None;
+}
+
 fn process_sounds(
     mut commands: Commands,
     sound_query: Query<(&PlayerSound, Entity)>,
-    audio_handles: Res<AudioHandles>,
-    audio: Res<Audio>,
+    mut play_sound_events: EventWriter<PlaySound>,
+    clock: Res<GameClock>,
 ) {
-    let now = Instant::now();
     for (player_sound, entity) in sound_query.iter() {
-        if player_sound.time_to_play < now {
-            match player_sound.sound_type {
-                PlayerSoundType::Cock => {
-                    audio.play(Handle::weak(audio_handles.cock.id()));
-                }
-                PlayerSoundType::Gunshot => {
-                    audio.play(Handle::weak(audio_handles.gunshot.id()));
-                }
-                PlayerSoundType::Shell => {
-                    audio.play(Handle::weak(audio_handles.shell.id()));
-                }
-                PlayerSoundType::Bite => {
-                    audio.play(Handle::weak(audio_handles.bite.id()));
-                }
-                PlayerSoundType::Empty => {
-                    audio.play(Handle::weak(audio_handles.empty.id()));
-                }
-            }
-
+        if player_sound.time_to_play <= clock.elapsed() {
+            play_sound_events.send(PlaySound(player_sound.sound_type));
             commands.entity(entity).despawn();
         }
     }
 }
 
-fn spawn_shotgun_blast(
+fn spawn_blast(
     mut commands: Commands,
+    weapon: &Weapon,
     player_transform: &Transform,
     mut rng: ResMut<GlobalRng>,
     mut force: Mut<ExternalForce>,
@@ -280,79 +449,45 @@ fn spawn_shotgun_blast(
         player_transform.rotation.to_euler(EulerRot::XYZ).2 + (std::f32::consts::PI / 2.),
     );
 
-    let recoil_direction = Vec2::new(player_facing.x * -1.0, player_facing.y * -1.0);
-    force.force = recoil_direction * 300.0;
+    let recoil_direction = -player_facing;
+    force.force = recoil_direction * weapon.recoil;
 
     let initial_bullet_location = Vec2::new(
         player_transform.translation.x + (player_facing.x * BULLET_SPAWN_DISTANCE_MULTIPLIER),
         player_transform.translation.y + (player_facing.y * BULLET_SPAWN_DISTANCE_MULTIPLIER),
     );
 
-    spawn_bullet(
-        &mut commands,
-        initial_bullet_location,
-        player_facing,
-        get_shot_randomness(&mut rng),
-        get_shot_randomness(&mut rng),
-    );
-    spawn_bullet(
-        &mut commands,
-        initial_bullet_location,
-        player_facing,
-        get_shot_randomness(&mut rng),
-        get_shot_randomness(&mut rng),
-    );
-    spawn_bullet(
-        &mut commands,
-        initial_bullet_location,
-        player_facing,
-        get_shot_randomness(&mut rng),
-        get_shot_randomness(&mut rng),
-    );
-    spawn_bullet(
-        &mut commands,
-        initial_bullet_location,
-        player_facing,
-        get_shot_randomness(&mut rng),
-        get_shot_randomness(&mut rng),
-    );
-    spawn_bullet(
-        &mut commands,
-        initial_bullet_location,
-        player_facing,
-        get_shot_randomness(&mut rng),
-        get_shot_randomness(&mut rng),
-    );
+    for _ in 0..weapon.pellets {
+        spawn_bullet(
+            &mut commands,
+            weapon,
+            initial_bullet_location,
+            player_facing,
+            get_shot_randomness(&mut rng, weapon.spread),
+            get_shot_randomness(&mut rng, weapon.spread),
+        );
+    }
 }
 
-fn get_shot_randomness(rng: &mut ResMut<GlobalRng>) -> f32 {
-    rng.f32_normalized() * 0.2
+fn get_shot_randomness(rng: &mut ResMut<GlobalRng>, spread: f32) -> f32 {
+    rng.f32_normalized() * spread
 }
 
 fn spawn_bullet(
     commands: &mut Commands,
+    weapon: &Weapon,
     initial_bullet_location: Vec2,
     player_facing: Vec2,
     shot_offset_x: f32,
     shot_offset_y: f32,
 ) {
-    const BULLET_FORCE_MULTIPLIER: f32 = 10.0;
-
     let bullet_force = Vec2::new(
-        (player_facing.x + shot_offset_x) * BULLET_FORCE_MULTIPLIER,
-        (player_facing.y + shot_offset_y) * BULLET_FORCE_MULTIPLIER,
+        (player_facing.x + shot_offset_x) * weapon.bullet_force,
+        (player_facing.y + shot_offset_y) * weapon.bullet_force,
     );
 
     commands
         .spawn(RigidBody::Dynamic)
-        .insert(SpriteBundle {
-            sprite: Sprite {
-                color: Color::BLACK,
-                custom_size: Some(Vec2::new(5.0, 5.0)),
-                ..default()
-            },
-            ..default()
-        })
         .insert(TransformBundle::from(Transform::from_xyz(
             initial_bullet_location.x,
             initial_bullet_location.y,
@@ -366,24 +501,37 @@ fn spawn_bullet(
         })
         .insert(ActiveEvents::COLLISION_EVENTS)
         .insert(CollisionGroups::new(
-            Group::GROUP_1,
-            Group::ALL.difference(Group::GROUP_1),
+            BULLET_GROUP,
+            Group::ALL.difference(BULLET_GROUP | PICKUP_GROUP),
         ))
         .insert(Collider::cuboid(1.0, 1.0))
         .insert(ColliderMassProperties::Density(0.1))
         .insert(Bullet {
+            damage: weapon.damage,
             ..Default::default()
         });
 }
 
 fn despawn_collided_bullets(mut commands: Commands, bullet_query: Query<(&Bullet, Entity)>) {
     for (bullet, entity) in bullet_query.iter() {
-        if bullet.collided == true {
+        if bullet.collided {
             commands.entity(entity).despawn_recursive();
         }
     }
 }
 
+fn take_player_hits(
+    mut player_hit_events: EventReader<PlayerHit>,
+    mut player_query: Query<&mut Player>,
+) {
+    let Ok(mut player) = player_query.get_single_mut() else {
+        return;
+    };
+    for hit in player_hit_events.iter() {
+        player.health -= hit.damage;
+    }
+}
+
 fn detect_player_death(mut next_state: ResMut<NextState<AppState>>, player_query: Query<&Player>) {
     for player in player_query.iter() {
         if player.health <= 0 {
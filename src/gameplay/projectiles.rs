@@ -0,0 +1,142 @@
+use crate::states::AppState;
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use bevy_rapier2d::prelude::*;
+
+use super::awareness::{update_enemy_states, EnemyState};
+use super::bestiary::{Bestiary, EnemyAi};
+use super::clock::GameClock;
+use super::enemies::Enemy;
+use super::physics::{PICKUP_GROUP, SPIT_GROUP};
+use super::player::Player;
+use super::FixedSet;
+
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            (
+                spit_at_player.after(update_enemy_states),
+                despawn_spent_spit,
+            )
+                .in_base_set(FixedSet::Update)
+                .distributive_run_if(in_state(AppState::InGame))
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
+    }
+}
+
+/// Sprites for enemy projectiles.
+pub struct ProjectileSpritePlugin;
+
+impl Plugin for ProjectileSpritePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(attach_spit_sprite.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+/// How long a glob flies before drying up, if it hits nothing.
+const SPIT_LIFETIME: Duration = Duration::from_secs(4);
+const SPIT_COLOR: Color = Color::rgb(0.45, 0.8, 0.2);
+
+/// A glob spat at the player.
+#[derive(Component)]
+pub struct Spit {
+    /// Health taken off the player it hits.
+    pub damage: i32,
+    /// Hit something, and is despawned next step.
+    pub collided: bool,
+    /// Game time it dries up.
+    expires: Duration,
+}
+
+/// On enemies with a spit attack.
+#[derive(Component, Default)]
+pub struct Spitter {
+    /// Game time it can spit again.
+    ready_at: Duration,
+}
+
+/// Ranged enemies spit whenever they can see the player within their range
+/// and have got their breath back.
+fn spit_at_player(
+    mut commands: Commands,
+    mut spitter_query: Query<(&Enemy, &mut Spitter, &Transform)>,
+    player_query: Query<&Transform, With<Player>>,
+    clock: Res<GameClock>,
+    bestiary: Res<Bestiary>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player = player_transform.translation.truncate();
+    let now = clock.elapsed();
+
+    for (enemy, mut spitter, transform) in spitter_query.iter_mut() {
+        let archetype = &bestiary.archetypes[enemy.archetype()];
+        let EnemyAi::Ranged {
+            range,
+            spit: Some(spit),
+        } = archetype.ai
+        else {
+            continue;
+        };
+        let position = transform.translation.truncate();
+        let in_sight = matches!(enemy.state, EnemyState::Chasing { in_sight: true, .. });
+        if !in_sight || now < spitter.ready_at || position.distance(player) > range {
+            continue;
+        }
+
+        let toward = (player - position).normalize_or_zero();
+        // Starts clear of the spitter so it doesn't hide inside it.
+        let start = position + toward * (archetype.radius + spit.radius + 2.0);
+        commands
+            .spawn(RigidBody::Dynamic)
+            .insert(TransformBundle::from(Transform::from_xyz(
+                start.x, start.y, 4.0,
+            )))
+            .insert(Sleeping::disabled())
+            .insert(Velocity::linear(toward * spit.speed))
+            .insert(Collider::ball(spit.radius))
+            .insert(Sensor)
+            .insert(ActiveEvents::COLLISION_EVENTS)
+            .insert(CollisionGroups::new(
+                SPIT_GROUP,
+                Group::ALL.difference(SPIT_GROUP | PICKUP_GROUP),
+            ))
+            .insert(Spit {
+                damage: spit.damage,
+                collided: false,
+                expires: now + SPIT_LIFETIME,
+            });
+        spitter.ready_at = now + Duration::from_secs_f32(spit.every_secs.max(0.0));
+    }
+}
+
+fn despawn_spent_spit(
+    mut commands: Commands,
+    spit_query: Query<(&Spit, Entity)>,
+    clock: Res<GameClock>,
+) {
+    for (spit, entity) in spit_query.iter() {
+        if spit.collided || clock.elapsed() >= spit.expires {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn attach_spit_sprite(mut commands: Commands, spit_query: Query<(&Collider, Entity), Added<Spit>>) {
+    for (collider, entity) in spit_query.iter() {
+        let size = collider.as_ball().map_or(8.0, |ball| ball.radius() * 2.0);
+        commands.entity(entity).insert((
+            Sprite {
+                color: SPIT_COLOR,
+                custom_size: Some(Vec2::splat(size)),
+                ..default()
+            },
+            Handle::<Image>::default(),
+            VisibilityBundle::default(),
+        ));
+    }
+}
@@ -0,0 +1,226 @@
+use crate::states::AppState;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use super::enemies::Enemy;
+use super::FixedSet;
+
+pub struct SpatialPlugin;
+
+impl Plugin for SpatialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EnemyGrid>();
+        app.add_system(
+            rebuild_enemy_grid
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .in_base_set(FixedSet::Update)
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// Side of a grid cell, about the distance enemies look for neighbours at.
+const CELL_SIZE: f32 = 50.0;
+
+/// A living enemy, as stored in the grid.
+#[derive(Clone, Copy, Debug)]
+pub struct Neighbour {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub archetype: usize,
+}
+
+/// Living enemies bucketed by position, so finding the ones near a point
+/// only looks at the cells around it. Rebuilt every step.
+#[derive(Resource, Default)]
+pub struct EnemyGrid {
+    cells: HashMap<IVec2, Vec<Neighbour>>,
+    len: usize,
+    /// Corners of the occupied cells.
+    min: IVec2,
+    max: IVec2,
+}
+
+impl EnemyGrid {
+    fn cell(position: Vec2) -> IVec2 {
+        (position / CELL_SIZE).floor().as_ivec2()
+    }
+
+    /// Replaces the contents with `enemies`. Keeps the buckets of cells that
+    /// were occupied last time, so a steady crowd doesn't reallocate.
+    pub fn rebuild(&mut self, enemies: impl IntoIterator<Item = Neighbour>) {
+        self.cells.retain(|_, bucket| {
+            let occupied = !bucket.is_empty();
+            bucket.clear();
+            occupied
+        });
+        self.len = 0;
+        self.min = IVec2::splat(i32::MAX);
+        self.max = IVec2::splat(i32::MIN);
+
+        for enemy in enemies {
+            let cell = Self::cell(enemy.position);
+            self.cells.entry(cell).or_default().push(enemy);
+            self.len += 1;
+            self.min = self.min.min(cell);
+            self.max = self.max.max(cell);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Every enemy, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &Neighbour> {
+        self.cells.values().flatten()
+    }
+
+    /// Enemies closer than `radius` to `center`, including any standing on it.
+    pub fn within(&self, center: Vec2, radius: f32) -> impl Iterator<Item = &Neighbour> {
+        let from = Self::cell(center - radius).max(self.min);
+        let to = Self::cell(center + radius).min(self.max);
+        (from.y..=to.y)
+            .flat_map(move |y| (from.x..=to.x).map(move |x| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(move |enemy| enemy.position.distance_squared(center) < radius * radius)
+    }
+
+    /// Up to `k` enemies closest to `center`, nearest first. Searches rings
+    /// of cells outward until nothing further out could be closer.
+    pub fn nearest(&self, center: Vec2, k: usize) -> Vec<&Neighbour> {
+        let mut found: Vec<(f32, &Neighbour)> = Vec::new();
+        if k == 0 || self.is_empty() {
+            return Vec::new();
+        }
+
+        let origin = Self::cell(center);
+        let reach = (origin - self.min)
+            .max(self.max - origin)
+            .max_element()
+            .max(0);
+        for ring in 0..=reach {
+            for cell in ring_cells(origin, ring) {
+                if let Some(bucket) = self.cells.get(&cell) {
+                    found.extend(
+                        bucket
+                            .iter()
+                            .map(|enemy| (enemy.position.distance_squared(center), enemy)),
+                    );
+                }
+            }
+            if found.len() >= k {
+                found.select_nth_unstable_by(k - 1, |a, b| a.0.total_cmp(&b.0));
+                found.truncate(k);
+                // Anything in the next ring is at least this far away.
+                let next_ring = ring as f32 * CELL_SIZE;
+                if found[k - 1].0 <= next_ring * next_ring {
+                    break;
+                }
+            }
+        }
+
+        found.sort_by(|a, b| a.0.total_cmp(&b.0));
+        found.into_iter().take(k).map(|(_, enemy)| enemy).collect()
+    }
+}
+
+/// Cells exactly `ring` steps away from `origin`, counting diagonals as one.
+fn ring_cells(origin: IVec2, ring: i32) -> impl Iterator<Item = IVec2> {
+    (-ring..=ring)
+        .flat_map(move |y| (-ring..=ring).map(move |x| IVec2::new(x, y)))
+        .filter(move |offset| offset.x.abs() == ring || offset.y.abs() == ring)
+        .map(move |offset| origin + offset)
+}
+
+pub fn rebuild_enemy_grid(
+    mut grid: ResMut<EnemyGrid>,
+    enemy_query: Query<(&Enemy, &Transform, Entity)>,
+) {
+    grid.rebuild(
+        enemy_query
+            .iter()
+            .filter(|(enemy, ..)| enemy.is_alive())
+            .map(|(enemy, transform, entity)| Neighbour {
+                entity,
+                position: transform.translation.truncate(),
+                archetype: enemy.archetype(),
+            }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_turborand::prelude::*;
+
+    /// A clump near the origin and a thin spread around it, so queries hit
+    /// both crowded and empty cells.
+    fn crowd(seed: u64, count: u32) -> Vec<Neighbour> {
+        let mut rng = GlobalRng::with_seed(seed);
+        (0..count)
+            .map(|index| {
+                let spread = if index % 3 == 0 { 800.0 } else { 120.0 };
+                Neighbour {
+                    entity: Entity::from_raw(index),
+                    position: (Vec2::new(rng.f32(), rng.f32()) - 0.5) * spread,
+                    archetype: 0,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn queries_match_checking_every_enemy() {
+        for seed in 0..20 {
+            let enemies = crowd(seed, 300);
+            let mut grid = EnemyGrid::default();
+            // Rebuilt twice so the kept buckets are exercised too.
+            grid.rebuild(crowd(seed + 100, 50));
+            grid.rebuild(enemies.iter().copied());
+            assert_eq!(grid.len(), enemies.len());
+
+            let centers = enemies
+                .iter()
+                .step_by(10)
+                .map(|enemy| enemy.position)
+                .chain([Vec2::ZERO, Vec2::new(1000.0, -700.0), Vec2::splat(25.0)]);
+            for center in centers {
+                for radius in [0.0, 10.0, 50.0, 75.0, 400.0] {
+                    let mut found: Vec<_> = grid
+                        .within(center, radius)
+                        .map(|enemy| enemy.entity)
+                        .collect();
+                    let mut expected: Vec<_> = enemies
+                        .iter()
+                        .filter(|enemy| enemy.position.distance_squared(center) < radius * radius)
+                        .map(|enemy| enemy.entity)
+                        .collect();
+                    found.sort();
+                    expected.sort();
+                    assert_eq!(found, expected, "within {radius} of {center}");
+                }
+
+                let mut by_distance: Vec<f32> = enemies
+                    .iter()
+                    .map(|enemy| enemy.position.distance_squared(center))
+                    .collect();
+                by_distance.sort_by(f32::total_cmp);
+                for k in [0, 1, 8, 40, 400] {
+                    let found: Vec<f32> = grid
+                        .nearest(center, k)
+                        .iter()
+                        .map(|enemy| enemy.position.distance_squared(center))
+                        .collect();
+                    let expected = &by_distance[..k.min(by_distance.len())];
+                    assert_eq!(found, expected, "nearest {k} to {center}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::Duration;
+use serde::Deserialize;
+
+use super::data::DataAsset;
+use super::enemies::ARENA_HALF_SIZE;
+use super::pickups::DropTable;
+
+/// How each stage plays out, from stage 1 on. Stages past the end of the list
+/// are endless play, following `Stage::endless`, and `boss` replaces every
+/// few stages.
+#[derive(Resource, Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "5e9a3c17-2d84-4b6f-8e1a-9c0f7d2b4a68"]
+pub struct Stages {
+    #[serde(default)]
+    pub spawns: SpawnRules,
+    pub stages: Vec<Stage>,
+    #[serde(default)]
+    pub boss: Option<BossStage>,
+}
+
+/// Every `every`th stage is `stage` instead, a boss fight.
+#[derive(Deserialize, Clone, Debug)]
+pub struct BossStage {
+    pub every: u32,
+    pub stage: Stage,
+}
+
+/// Where enemies may appear, for every stage.
+#[derive(Deserialize, Clone, Debug)]
+pub struct SpawnRules {
+    /// Enemies never appear closer than this to the player.
+    #[serde(default = "default_min_distance")]
+    pub min_distance: f32,
+    /// Seconds a marker shows where an enemy is about to appear.
+    #[serde(default = "default_telegraph")]
+    pub telegraph_secs: f32,
+    /// Spots waves can spawn at by name.
+    #[serde(default)]
+    pub points: Vec<SpawnPoint>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SpawnPoint {
+    pub name: String,
+    pub position: (f32, f32),
+}
+
+/// Where a wave's enemies appear.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub enum SpawnAt {
+    /// Along the arena walls.
+    #[default]
+    Edges,
+    /// Anywhere in the arena.
+    Anywhere,
+    /// At one of the named spawn points.
+    Points(Vec<String>),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Stage {
+    pub waves: Vec<Wave>,
+    /// Seconds between clearing this stage and the next one starting.
+    #[serde(default = "default_break")]
+    pub break_secs: f32,
+    /// Rolled when the stage is cleared, instead of the `stage_clear` drop
+    /// table.
+    #[serde(default)]
+    pub reward: Option<DropTable>,
+    /// Added to the score when the stage is cleared.
+    #[serde(default)]
+    pub bonus: i32,
+}
+
+/// A group of enemies arriving at a steady rate.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Wave {
+    /// Seconds after the stage starts.
+    #[serde(default)]
+    pub start_secs: f32,
+    /// Seconds over which the wave's enemies spawn.
+    pub duration_secs: f32,
+    pub count: u32,
+    /// Archetypes to spawn, by name. Empty picks from the bestiary by stage.
+    #[serde(default)]
+    pub mix: Vec<MixEntry>,
+    #[serde(default)]
+    pub spawn_at: SpawnAt,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct MixEntry {
+    pub archetype: String,
+    pub weight: u32,
+}
+
+fn default_break() -> f32 {
+    3.0
+}
+
+fn default_min_distance() -> f32 {
+    200.0
+}
+
+fn default_telegraph() -> f32 {
+    1.0
+}
+
+impl Default for SpawnRules {
+    fn default() -> Self {
+        Self {
+            min_distance: default_min_distance(),
+            telegraph_secs: default_telegraph(),
+            points: Vec::new(),
+        }
+    }
+}
+
+impl SpawnRules {
+    pub fn telegraph(&self) -> Duration {
+        Duration::from_secs_f32(self.telegraph_secs.max(0.0))
+    }
+
+    pub fn point(&self, name: &str) -> Option<Vec2> {
+        self.points
+            .iter()
+            .find(|point| point.name == name)
+            .map(|point| Vec2::new(point.position.0, point.position.1))
+    }
+}
+
+impl Stages {
+    pub fn is_boss_stage(&self, stage: u32) -> bool {
+        self.boss
+            .as_ref()
+            .is_some_and(|boss| boss.every > 0 && stage.is_multiple_of(boss.every))
+    }
+
+    pub fn get(&self, stage: u32) -> Stage {
+        if let Some(boss) = self.boss.as_ref().filter(|_| self.is_boss_stage(stage)) {
+            return boss.stage.clone();
+        }
+        stage
+            .checked_sub(1)
+            .and_then(|index| self.stages.get(index as usize))
+            .cloned()
+            .unwrap_or_else(|| Stage::endless(stage))
+    }
+}
+
+impl Stage {
+    /// Ten enemies per stage number, ramping up over 30 seconds.
+    pub fn endless(stage: u32) -> Self {
+        Self {
+            waves: vec![Wave {
+                start_secs: 0.0,
+                duration_secs: 30.0,
+                count: stage * 10,
+                mix: Vec::new(),
+                spawn_at: SpawnAt::Edges,
+            }],
+            break_secs: default_break(),
+            reward: None,
+            bonus: 0,
+        }
+    }
+
+    pub fn break_length(&self) -> Duration {
+        Duration::from_secs_f32(self.break_secs.max(0.0))
+    }
+}
+
+impl Wave {
+    /// How many of the wave's enemies should have spawned this long into the
+    /// stage.
+    pub fn due(&self, since_stage_start: Duration) -> u32 {
+        let elapsed = since_stage_start.as_secs_f32() - self.start_secs;
+        if elapsed < 0.0 {
+            return 0;
+        }
+        if elapsed >= self.duration_secs {
+            return self.count;
+        }
+        (self.count as f32 * elapsed / self.duration_secs).ceil() as u32
+    }
+}
+
+impl DataAsset for Stages {
+    const PATH: &'static str = "stages/stages.stages.ron";
+
+    fn validate(&self) -> Result<(), String> {
+        // Spawns fall back to the wall opposite the player, which is at
+        // least this far away.
+        let max_distance = ARENA_HALF_SIZE.min_element();
+        if self.spawns.min_distance > max_distance {
+            return Err(format!(
+                "spawn min_distance can't be above {max_distance}, or nowhere is far enough"
+            ));
+        }
+
+        let unknown_point = self
+            .stages
+            .iter()
+            .chain(self.boss.iter().map(|boss| &boss.stage))
+            .flat_map(|stage| stage.waves.iter())
+            .filter_map(|wave| match &wave.spawn_at {
+                SpawnAt::Points(names) => Some(names),
+                _ => None,
+            })
+            .flatten()
+            .find(|name| self.spawns.point(name).is_none());
+        if let Some(name) = unknown_point {
+            return Err(format!("unknown spawn point {name}"));
+        }
+
+        let named = self
+            .stages
+            .iter()
+            .enumerate()
+            .map(|(index, stage)| (format!("stage {}", index + 1), stage))
+            .chain(
+                self.boss
+                    .iter()
+                    .map(|boss| ("the boss stage".to_string(), &boss.stage)),
+            );
+        for (name, stage) in named {
+            if stage.waves.iter().all(|wave| wave.count == 0) {
+                return Err(format!("{name} spawns no enemies"));
+            }
+            if stage
+                .waves
+                .iter()
+                .flat_map(|wave| wave.mix.iter())
+                .any(|entry| entry.weight == 0)
+            {
+                return Err(format!("{name} has a mix weight of 0"));
+            }
+            if stage.reward.iter().flatten().any(|drop| drop.weight == 0) {
+                return Err(format!("{name} has a reward weight of 0"));
+            }
+        }
+        Ok(())
+    }
+}
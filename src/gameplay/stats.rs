@@ -0,0 +1,204 @@
+use crate::states::AppState;
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use std::collections::BTreeMap;
+
+use super::clock::GameClock;
+use super::enemies::EnemySpawner;
+use super::events::{
+    DamageDealt, EnemyKilled, HitCause, PlayerHit, ReloadStarted, ShotFired, SpitShotDown,
+};
+use super::player::Player;
+use super::FixedSet;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>();
+        app.add_system(reset_run_stats.in_schedule(OnEnter(AppState::InGame)));
+        app.add_system(
+            track_time_alive
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .in_base_set(FixedSet::Update)
+                .run_if(in_state(AppState::InGame)),
+        );
+        app.add_systems(
+            (
+                count_shots,
+                count_reloads,
+                count_hits,
+                count_player_hits,
+                count_shot_down_spit,
+                count_kills,
+            )
+                .in_base_set(FixedSet::Reactions)
+                .distributive_run_if(in_state(AppState::InGame))
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
+    }
+}
+
+/// Numbers from the current run, for balancing the shotgun and the enemy
+/// counts. Shown on the scoreboard and logged at the end of a headless run.
+#[derive(Resource, Default, Debug)]
+pub struct RunStats {
+    pub shots_fired: u32,
+    pub pellets_fired: u32,
+    /// Pellets that hit an enemy. The rest hit a wall or are still flying.
+    pub pellets_hit: u32,
+    pub kills_per_stage: BTreeMap<u32, u32>,
+    pub bites: u32,
+    /// From bites and spit.
+    pub damage_taken: i32,
+    pub spit_hits: u32,
+    pub spit_damage: i32,
+    /// Globs a pellet hit before they reached the player.
+    pub spit_shot_down: u32,
+    pub time_alive: Duration,
+    pub reloads: u32,
+}
+
+impl RunStats {
+    pub fn pellets_missed(&self) -> u32 {
+        self.pellets_fired.saturating_sub(self.pellets_hit)
+    }
+
+    /// Share of pellets that hit an enemy, from 0 to 1.
+    pub fn accuracy(&self) -> f32 {
+        if self.pellets_fired == 0 {
+            0.0
+        } else {
+            self.pellets_hit as f32 / self.pellets_fired as f32
+        }
+    }
+
+    pub fn damage_per_bite(&self) -> f32 {
+        if self.bites == 0 {
+            0.0
+        } else {
+            (self.damage_taken - self.spit_damage) as f32 / self.bites as f32
+        }
+    }
+
+    pub fn record_kill(&mut self, stage: u32) {
+        *self.kills_per_stage.entry(stage).or_default() += 1;
+    }
+
+    /// One line per stat, in the order the scoreboard shows them.
+    pub fn summary(&self) -> Vec<String> {
+        let kills = if self.kills_per_stage.is_empty() {
+            "none".to_string()
+        } else {
+            self.kills_per_stage
+                .iter()
+                .map(|(stage, kills)| format!("{stage}: {kills}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+        };
+
+        vec![
+            format!(
+                "Time alive {}:{:02}",
+                self.time_alive.as_secs() / 60,
+                self.time_alive.as_secs() % 60
+            ),
+            format!(
+                "Shots fired {} - Reloads {}",
+                self.shots_fired, self.reloads
+            ),
+            format!(
+                "Pellets hit {} - missed {} ({:.0}%)",
+                self.pellets_hit,
+                self.pellets_missed(),
+                self.accuracy() * 100.0
+            ),
+            format!(
+                "Bites {} - {:.1} damage per bite",
+                self.bites,
+                self.damage_per_bite()
+            ),
+            format!(
+                "Spit hits {} - shot down {}",
+                self.spit_hits, self.spit_shot_down
+            ),
+            format!("Kills per stage  {kills}"),
+        ]
+    }
+}
+
+/// A fresh run starts whenever `InGame` is entered without a spawner left
+/// over; coming back from the pause menu or a cutscene keeps the stats.
+fn reset_run_stats(mut stats: ResMut<RunStats>, enemy_spawner_query: Query<&EnemySpawner>) {
+    if enemy_spawner_query.is_empty() {
+        *stats = RunStats::default();
+    }
+}
+
+fn track_time_alive(
+    mut stats: ResMut<RunStats>,
+    clock: Res<GameClock>,
+    player_query: Query<&Player>,
+) {
+    if player_query.iter().any(|player| player.health > 0) {
+        stats.time_alive += clock.step();
+    }
+}
+
+fn count_shots(mut stats: ResMut<RunStats>, mut shot_fired_events: EventReader<ShotFired>) {
+    for shot in shot_fired_events.iter() {
+        stats.shots_fired += 1;
+        stats.pellets_fired += shot.pellets;
+    }
+}
+
+fn count_reloads(
+    mut stats: ResMut<RunStats>,
+    mut reload_started_events: EventReader<ReloadStarted>,
+) {
+    stats.reloads += reload_started_events.iter().count() as u32;
+}
+
+fn count_hits(mut stats: ResMut<RunStats>, mut damage_events: EventReader<DamageDealt>) {
+    // A pellet can touch two enemies in one step; count it once.
+    let mut pellets = Vec::new();
+    for damage in damage_events.iter() {
+        if !pellets.contains(&damage.source) {
+            pellets.push(damage.source);
+            stats.pellets_hit += 1;
+        }
+    }
+}
+
+fn count_player_hits(mut stats: ResMut<RunStats>, mut player_hit_events: EventReader<PlayerHit>) {
+    for hit in player_hit_events.iter() {
+        stats.damage_taken += hit.damage;
+        match hit.cause {
+            HitCause::Bite => stats.bites += 1,
+            HitCause::Spit => {
+                stats.spit_hits += 1;
+                stats.spit_damage += hit.damage;
+            }
+        }
+    }
+}
+
+fn count_shot_down_spit(
+    mut stats: ResMut<RunStats>,
+    mut spit_shot_down_events: EventReader<SpitShotDown>,
+) {
+    stats.spit_shot_down += spit_shot_down_events.iter().count() as u32;
+}
+
+fn count_kills(
+    mut stats: ResMut<RunStats>,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    enemy_spawner_query: Query<&EnemySpawner>,
+) {
+    let Ok(enemy_spawner) = enemy_spawner_query.get_single() else {
+        return;
+    };
+    for _ in enemy_killed_events.iter() {
+        stats.record_kill(enemy_spawner.stage);
+    }
+}
@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+
+use super::bestiary::Steering;
+use super::spatial::EnemyGrid;
+
+/// Distance from the player that surrounding enemies spread out at.
+const SURROUND_DISTANCE: f32 = 90.0;
+/// Spaces flank angles evenly however many enemies there are.
+const GOLDEN_ANGLE: f32 = 2.399_963;
+
+/// Combines the pull along `heading` with the archetype's steering
+/// behaviours, capped at full strength.
+pub fn steer(
+    entity: Entity,
+    position: Vec2,
+    archetype: usize,
+    heading: Vec2,
+    player: Vec2,
+    weights: &Steering,
+    grid: &EnemyGrid,
+) -> Vec2 {
+    let radius = weights.neighbour_radius;
+    let nearby = grid
+        .within(position, radius)
+        .filter(|neighbour| neighbour.entity != entity);
+
+    let mut separation = Vec2::ZERO;
+    let mut pack_sum = Vec2::ZERO;
+    let mut pack_size = 0;
+    for neighbour in nearby {
+        let away = position - neighbour.position;
+        let distance = away.length();
+        // Stronger the closer they are.
+        separation += away.normalize_or_zero() * (1.0 - distance / radius);
+        if neighbour.archetype == archetype {
+            pack_sum += neighbour.position;
+            pack_size += 1;
+        }
+    }
+
+    let cohesion = if pack_size > 0 {
+        let center = pack_sum / pack_size as f32;
+        (center - position) / radius
+    } else {
+        Vec2::ZERO
+    };
+
+    let flank = player + Vec2::from_angle(entity.index() as f32 * GOLDEN_ANGLE) * SURROUND_DISTANCE;
+    // Only matters while the enemy is still further out than its flank.
+    let surround = if position.distance(player) > SURROUND_DISTANCE {
+        (flank - position).normalize_or_zero()
+    } else {
+        Vec2::ZERO
+    };
+
+    (heading
+        + separation.clamp_length_max(1.0) * weights.separation
+        + cohesion.clamp_length_max(1.0) * weights.cohesion
+        + surround * weights.surround)
+        .clamp_length_max(1.0)
+}
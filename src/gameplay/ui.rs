@@ -1,17 +1,22 @@
 use crate::states::AppState;
 use bevy::prelude::*;
-use instant::{Duration, Instant};
+use bevy::utils::Duration;
 
-use super::{EnemySpawner, Player};
+use super::bestiary::Bestiary;
+use super::boss::Boss;
+use super::weapon::Arsenal;
+use super::{Enemy, EnemySpawner, GameClock, Player, PlayerHit, StageStarted};
 
 pub struct UIPlugin;
 
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(create_menu.in_schedule(OnEnter(AppState::InGame)));
-        app.add_system(update_shell_count.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(update_weapons.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(update_reload_progress.in_set(OnUpdate(AppState::InGame)));
         app.add_system(update_score.in_set(OnUpdate(AppState::InGame)));
         app.add_system(update_hp.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(update_boss_bar.in_set(OnUpdate(AppState::InGame)));
         app.add_system(handle_stage_change.in_set(OnUpdate(AppState::InGame)));
         app.add_system(handle_remove_stage.in_set(OnUpdate(AppState::InGame)));
         app.add_system(destroy_menu.in_schedule(OnExit(AppState::InGame)));
@@ -21,12 +26,37 @@ impl Plugin for UIPlugin {
 #[derive(Resource)]
 struct MenuEntities {
     hp: Entity,
-    bullets: Entity,
+    weapons: Entity,
     score: Entity,
+    boss: Entity,
 }
 
+/// One section per weapon in the arsenal, the one in hand drawn darker.
 #[derive(Component)]
-struct Bullets;
+struct Weapons;
+
+const HELD_WEAPON_COLOR: Color = Color::rgb(0.0, 0.0, 0.0);
+const STOWED_WEAPON_COLOR: Color = Color::rgb(0.45, 0.45, 0.45);
+
+/// Bar under the weapons, shown while reloading.
+#[derive(Component)]
+struct ReloadProgress;
+
+/// Fills `ReloadProgress` as the current reload step completes.
+#[derive(Component)]
+struct ReloadProgressFill;
+
+/// Boss name and health along the bottom, shown while a boss is alive.
+#[derive(Component)]
+struct BossBar;
+
+#[derive(Component)]
+struct BossName;
+
+#[derive(Component)]
+struct BossBarFill;
+
+const BOSS_COLOR: Color = Color::rgb(0.6, 0.1, 0.1);
 
 #[derive(Component)]
 struct HP;
@@ -36,32 +66,60 @@ struct Score;
 
 #[derive(Component)]
 struct Stage {
-    shown: Instant,
+    shown: Duration,
 }
 
-fn create_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let bullets = commands
+fn create_menu(mut commands: Commands, asset_server: Res<AssetServer>, arsenal: Res<Arsenal>) {
+    let weapons = commands
         .spawn(NodeBundle {
             style: Style {
                 // center button
                 size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Start,
+                justify_content: JustifyContent::Start,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
                 ..default()
             },
             ..default()
         })
         .with_children(|parent| {
             parent
-                .spawn(TextBundle::from_section(
-                    "Shells: ",
-                    TextStyle {
-                        font: asset_server.load("fonts/pixelsplitter.ttf"),
-                        font_size: 40.0,
-                        color: Color::rgb(0.0, 0.0, 0.0),
+                .spawn(TextBundle::from_sections(arsenal.weapons.iter().map(
+                    |_| {
+                        TextSection::new(
+                            "",
+                            TextStyle {
+                                font: asset_server.load("fonts/pixelsplitter.ttf"),
+                                font_size: 24.0,
+                                color: STOWED_WEAPON_COLOR,
+                            },
+                        )
                     },
-                ))
-                .insert(Bullets);
+                )))
+                .insert(Weapons);
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(200.0), Val::Px(8.0)),
+                        ..default()
+                    },
+                    background_color: STOWED_WEAPON_COLOR.into(),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                })
+                .insert(ReloadProgress)
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            background_color: HELD_WEAPON_COLOR.into(),
+                            ..default()
+                        })
+                        .insert(ReloadProgressFill);
+                });
         })
         .id();
 
@@ -114,17 +172,123 @@ fn create_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                 .insert(Score);
         })
         .id();
-    commands.insert_resource(MenuEntities { bullets, hp, score });
+
+    let boss = commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::End,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                position_type: PositionType::Absolute,
+                padding: UiRect::bottom(Val::Px(16.0)),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .insert(BossBar)
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/pixelsplitter.ttf"),
+                        font_size: 24.0,
+                        color: BOSS_COLOR,
+                    },
+                ))
+                .insert(BossName);
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(400.0), Val::Px(14.0)),
+                        ..default()
+                    },
+                    background_color: STOWED_WEAPON_COLOR.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            background_color: BOSS_COLOR.into(),
+                            ..default()
+                        })
+                        .insert(BossBarFill);
+                });
+        })
+        .id();
+
+    commands.insert_resource(MenuEntities {
+        weapons,
+        hp,
+        score,
+        boss,
+    });
 }
 
-fn update_shell_count(
+fn update_weapons(
     player_query: Query<&Player>,
-    mut bullet_text_query: Query<&mut Text, With<Bullets>>,
+    mut weapon_text_query: Query<&mut Text, With<Weapons>>,
+    arsenal: Res<Arsenal>,
 ) {
     if let Ok(player) = player_query.get_single() {
-        let value = player.shells;
-        for mut text in &mut bullet_text_query {
-            text.sections[0].value = format!("Shells: {value:.2}");
+        for mut text in &mut weapon_text_query {
+            for (index, (section, weapon)) in text
+                .sections
+                .iter_mut()
+                .zip(arsenal.weapons.iter())
+                .enumerate()
+            {
+                section.value = format!(
+                    "{} {} {}/{} +{}   ",
+                    index + 1,
+                    weapon.name,
+                    player.ammo[index],
+                    weapon.magazine,
+                    player.reserve[index]
+                );
+                section.style.color = if index == player.weapon {
+                    HELD_WEAPON_COLOR
+                } else {
+                    STOWED_WEAPON_COLOR
+                };
+            }
+        }
+    }
+}
+
+fn update_reload_progress(
+    player_query: Query<&Player>,
+    mut progress_query: Query<&mut Visibility, With<ReloadProgress>>,
+    mut fill_query: Query<&mut Style, With<ReloadProgressFill>>,
+    arsenal: Res<Arsenal>,
+    clock: Res<GameClock>,
+) {
+    if let Ok(player) = player_query.get_single() {
+        let progress = player.reload_step_started.map(|step_started| {
+            let step = arsenal.weapons[player.weapon].reload.step();
+            if step.is_zero() {
+                1.0
+            } else {
+                (clock.elapsed().saturating_sub(step_started).as_secs_f32() / step.as_secs_f32())
+                    .min(1.0)
+            }
+        });
+
+        for mut visibility in &mut progress_query {
+            *visibility = if progress.is_some() {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+        for mut style in &mut fill_query {
+            style.size.width = Val::Percent(progress.unwrap_or(0.0) * 100.0);
         }
     }
 }
@@ -141,12 +305,94 @@ fn update_score(
     }
 }
 
-fn update_hp(player_query: Query<&Player>, mut hp_text_query: Query<&mut Text, With<HP>>) {
+fn update_boss_bar(
+    boss_query: Query<(&Enemy, &Boss)>,
+    mut bar_query: Query<&mut Visibility, With<BossBar>>,
+    mut name_query: Query<&mut Text, With<BossName>>,
+    mut fill_query: Query<&mut Style, With<BossBarFill>>,
+    bestiary: Res<Bestiary>,
+) {
+    let boss = boss_query.iter().find(|(enemy, _)| enemy.is_alive());
+
+    for mut visibility in &mut bar_query {
+        *visibility = if boss.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    let Some((enemy, boss)) = boss else {
+        return;
+    };
+    let archetype = &bestiary.archetypes[enemy.archetype()];
+    for mut text in &mut name_query {
+        text.sections[0].value = format!(
+            "{} - phase {}/{}",
+            archetype.name,
+            boss.phase + 1,
+            archetype.boss_phases.len()
+        );
+    }
+    for mut style in &mut fill_query {
+        style.size.width = Val::Percent(boss.health_fraction(enemy) * 100.0);
+    }
+}
+
+/// How long the HP text shows a heal or a hit.
+const HEAL_FLASH: Duration = Duration::from_millis(800);
+const HEAL_COLOR: Color = Color::rgb(0.1, 0.6, 0.1);
+const HIT_COLOR: Color = Color::rgb(0.7, 0.1, 0.1);
+
+/// Health last frame, and the last change with when it happened.
+#[derive(Default)]
+struct HealFlash {
+    last_health: Option<i32>,
+    /// Positive for a heal, negative for a hit.
+    change: Option<(i32, Duration)>,
+}
+
+fn update_hp(
+    player_query: Query<&Player>,
+    mut hp_text_query: Query<&mut Text, With<HP>>,
+    mut heal_flash: Local<HealFlash>,
+    mut player_hit_events: EventReader<PlayerHit>,
+    clock: Res<GameClock>,
+) {
     if let Ok(player) = player_query.get_single() {
         let value = player.health;
+        if let Some(last_health) = heal_flash.last_health {
+            if value > last_health {
+                heal_flash.change = Some((value - last_health, clock.elapsed()));
+            }
+        }
+        heal_flash.last_health = Some(value);
+        for hit in player_hit_events.iter() {
+            heal_flash.change = Some((-hit.damage, clock.elapsed()));
+        }
+
+        let change = heal_flash
+            .change
+            .filter(|(_, changed)| clock.elapsed() < *changed + HEAL_FLASH);
         for mut text in &mut hp_text_query {
-            text.sections[0].value = format!("HP: {value:.2}");
+            let section = &mut text.sections[0];
+            match change {
+                Some((amount, _)) if amount > 0 => {
+                    section.value = format!("HP: {value:.2} +{amount}");
+                    section.style.color = HEAL_COLOR;
+                }
+                Some((amount, _)) => {
+                    section.value = format!("HP: {value:.2} {amount}");
+                    section.style.color = HIT_COLOR;
+                }
+                None => {
+                    section.value = format!("HP: {value:.2}");
+                    section.style.color = Color::rgb(0.0, 0.0, 0.0);
+                }
+            }
         }
+    } else {
+        heal_flash.last_health = None;
     }
 }
 
@@ -155,9 +401,10 @@ fn destroy_menu(
     menu_data: Res<MenuEntities>,
     stage_text_query: Query<(&Stage, Entity)>,
 ) {
-    commands.entity(menu_data.bullets).despawn_recursive();
+    commands.entity(menu_data.weapons).despawn_recursive();
     commands.entity(menu_data.hp).despawn_recursive();
     commands.entity(menu_data.score).despawn_recursive();
+    commands.entity(menu_data.boss).despawn_recursive();
 
     for (_, entity) in stage_text_query.iter() {
         commands.entity(entity).despawn_recursive();
@@ -166,47 +413,48 @@ fn destroy_menu(
 
 fn handle_stage_change(
     mut commands: Commands,
-    enemy_spawner_query: Query<&EnemySpawner>,
-    mut last_stage: Local<u32>,
+    mut stage_started_events: EventReader<StageStarted>,
     asset_server: Res<AssetServer>,
+    clock: Res<GameClock>,
 ) {
-    if let Ok(enemy_spawner) = enemy_spawner_query.get_single() {
-        if *last_stage != enemy_spawner.stage {
-            *last_stage = enemy_spawner.stage;
-            let value = *last_stage;
-            commands
-                .spawn(NodeBundle {
-                    style: Style {
-                        // center button
-                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        position_type: PositionType::Absolute,
-                        ..default()
-                    },
+    for started in stage_started_events.iter() {
+        let value = started.stage;
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    // center button
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    position_type: PositionType::Absolute,
                     ..default()
-                })
-                .with_children(|parent| {
-                    parent
-                        .spawn(TextBundle::from_section(
-                            format!("Starting Stage {value:.2}"),
-                            TextStyle {
-                                font: asset_server.load("fonts/pixelsplitter.ttf"),
-                                font_size: 40.0,
-                                color: Color::rgb(0.0, 0.0, 0.0),
-                            },
-                        ))
-                        .insert(Stage {
-                            shown: Instant::now(),
-                        });
-                });
-        }
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                parent
+                    .spawn(TextBundle::from_section(
+                        format!("Starting Stage {value:.2}"),
+                        TextStyle {
+                            font: asset_server.load("fonts/pixelsplitter.ttf"),
+                            font_size: 40.0,
+                            color: Color::rgb(0.0, 0.0, 0.0),
+                        },
+                    ))
+                    .insert(Stage {
+                        shown: clock.elapsed(),
+                    });
+            });
     }
 }
 
-fn handle_remove_stage(mut commands: Commands, stage_text_query: Query<(&Stage, Entity)>) {
+fn handle_remove_stage(
+    mut commands: Commands,
+    stage_text_query: Query<(&Stage, Entity)>,
+    clock: Res<GameClock>,
+) {
     for (stage, entity) in stage_text_query.iter() {
-        if Instant::now() > stage.shown + Duration::from_secs(2) {
+        if clock.elapsed() > stage.shown + Duration::from_secs(2) {
             commands.entity(entity).despawn_recursive();
         }
     }
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::Duration;
+use serde::Deserialize;
+
+use super::data::DataAsset;
+use super::player::PlayerSoundType;
+
+/// Every weapon the player can carry.
+#[derive(Resource, Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "8a0b5c3e-6f2d-4a51-9d7e-2b4c1f0e9a63"]
+pub struct Arsenal {
+    pub weapons: Vec<Weapon>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Weapon {
+    pub name: String,
+    /// Bullets spawned per shot.
+    pub pellets: u32,
+    /// Up to this much is added to each axis of a pellet's direction.
+    pub spread: f32,
+    /// Force pushing the player back on each shot.
+    pub recoil: f32,
+    pub bullet_force: f32,
+    pub magazine: i32,
+    /// Spare rounds at the start of a run.
+    pub reserve: i32,
+    /// Most spare rounds that can be carried.
+    pub max_reserve: i32,
+    /// Game time after a shot before the next one. For the shotgun this is
+    /// the pump cycle, so it matches the last of its `fire_sounds`.
+    pub cooldown_ms: u64,
+    /// Keeps firing while the trigger is held.
+    #[serde(default)]
+    pub automatic: bool,
+    /// Health each bullet takes off an enemy.
+    pub damage: i32,
+    pub fire_sounds: Vec<WeaponSound>,
+    pub empty_sound: PlayerSoundType,
+    pub reload: Reload,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WeaponSound {
+    pub sound: PlayerSoundType,
+    /// Game time between the trigger and the sound.
+    pub delay_ms: u64,
+}
+
+impl Weapon {
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_millis(self.cooldown_ms)
+    }
+}
+
+impl WeaponSound {
+    pub fn delay(&self) -> Duration {
+        Duration::from_millis(self.delay_ms)
+    }
+}
+
+/// Reloading loads `rounds` every `step_ms` until the magazine is full.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Reload {
+    /// Rounds loaded per step, or `None` to fill the magazine in one step.
+    #[serde(default)]
+    pub rounds: Option<i32>,
+    #[serde(default)]
+    pub step_ms: u64,
+    /// Played as each step finishes.
+    #[serde(default)]
+    pub sounds: Vec<WeaponSound>,
+    /// Multiplies movement speed while reloading.
+    #[serde(default = "full_speed")]
+    pub move_speed: f32,
+}
+
+impl Reload {
+    pub fn step(&self) -> Duration {
+        Duration::from_millis(self.step_ms)
+    }
+}
+
+fn full_speed() -> f32 {
+    1.0
+}
+
+impl DataAsset for Arsenal {
+    const PATH: &'static str = "weapons/arsenal.weapons.ron";
+
+    fn validate(&self) -> Result<(), String> {
+        if self.weapons.is_empty() {
+            return Err("an arsenal needs at least one weapon".to_string());
+        }
+        for weapon in &self.weapons {
+            if weapon.magazine <= 0 {
+                return Err(format!("{} needs a magazine above 0", weapon.name));
+            }
+            if weapon.reserve > weapon.max_reserve {
+                return Err(format!(
+                    "{} starts with more reserve than it can carry",
+                    weapon.name
+                ));
+            }
+            if matches!(weapon.reload.rounds, Some(rounds) if rounds <= 0) {
+                return Err(format!("{} needs to reload above 0 rounds", weapon.name));
+            }
+        }
+        Ok(())
+    }
+}
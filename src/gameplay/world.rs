@@ -10,11 +10,36 @@ impl Plugin for WorldPlugin {
     }
 }
 
+/// Sprites for the furniture.
+pub struct WorldSpritePlugin;
+
+impl Plugin for WorldSpritePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(attach_furniture_sprite.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
 #[derive(Component)]
 pub struct WorldParent;
 
+/// Something in the arena that blocks the way, like a crib or a couch.
+#[derive(Component)]
+struct Furniture {
+    half_size: Vec2,
+}
+
+/// Where the furniture stands, and half its size.
+const FURNITURE: [(Vec2, Vec2); 3] = [
+    // crib
+    (Vec2::new(-200.0, 100.0), Vec2::new(50.0, 25.0)),
+    // table
+    (Vec2::new(200.0, 60.0), Vec2::new(35.0, 35.0)),
+    // couch
+    (Vec2::new(40.0, -150.0), Vec2::new(80.0, 20.0)),
+];
+
 fn spawn_world(mut commands: Commands, world_query: Query<&WorldParent>) {
-    for _ in world_query.iter() {
+    if !world_query.is_empty() {
         return;
     }
 
@@ -57,5 +82,34 @@ fn spawn_world(mut commands: Commands, world_query: Query<&WorldParent>) {
                 .insert(Sleeping::disabled())
                 .insert(Ccd::enabled())
                 .insert(CollisionGroups::new(Group::ALL, Group::ALL));
+
+            for (position, half_size) in FURNITURE {
+                parent
+                    .spawn(RigidBody::Fixed)
+                    .insert(Collider::cuboid(half_size.x, half_size.y))
+                    .insert(TransformBundle::from(Transform::from_xyz(
+                        position.x, position.y, 0.5,
+                    )))
+                    .insert(Sleeping::disabled())
+                    .insert(CollisionGroups::new(Group::ALL, Group::ALL))
+                    .insert(Furniture { half_size });
+            }
         });
 }
+
+fn attach_furniture_sprite(
+    mut commands: Commands,
+    furniture_query: Query<(Entity, &Furniture), Added<Furniture>>,
+) {
+    for (entity, furniture) in furniture_query.iter() {
+        commands.entity(entity).insert((
+            Sprite {
+                color: Color::rgb(0.45, 0.3, 0.2),
+                custom_size: Some(furniture.half_size * 2.0),
+                ..default()
+            },
+            Handle::<Image>::default(),
+            VisibilityBundle::default(),
+        ));
+    }
+}
@@ -0,0 +1,268 @@
+use crate::gameplay::{
+    Arsenal, Bestiary, DataAsset, DropTables, EnemyGrid, EnemySpawner, Player, RunStats, Stages,
+};
+use crate::states::AppState;
+use bevy::app::AppExit;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use bevy::time::{TimeSystem, TimeUpdateStrategy};
+use bevy::utils::{Duration, Instant};
+
+/// Runs the gameplay loop with no window, renderer or audio device for a fixed
+/// number of frames, each advancing the clock by exactly one fixed update.
+pub struct HeadlessPlugin {
+    pub ticks: u32,
+    /// Logs how long ticks and enemy grid queries took, for `--stress` runs.
+    pub timings: bool,
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        // There is no `AssetServer` to load the gameplay data with.
+        insert_data::<Arsenal>(app);
+        insert_data::<DropTables>(app);
+        insert_data::<Bestiary>(app);
+        insert_data::<Stages>(app);
+
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(LogPlugin::default())
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .insert_resource(HeadlessRun {
+                ticks_remaining: self.ticks,
+                clock: Instant::now(),
+                timings: self.timings.then(TickTimings::default),
+            })
+            .add_startup_system(start_game)
+            .add_system(advance_clock.in_base_set(CoreSet::First).before(TimeSystem))
+            .add_system(count_ticks.in_base_set(CoreSet::PostUpdate));
+    }
+}
+
+fn insert_data<T: DataAsset>(app: &mut App) {
+    match T::read_from_disk() {
+        Ok(data) => {
+            app.insert_resource(data);
+        }
+        Err(err) => panic!("{err}"),
+    }
+}
+
+#[derive(Resource)]
+struct HeadlessRun {
+    ticks_remaining: u32,
+    clock: Instant,
+    timings: Option<TickTimings>,
+}
+
+/// Wall-clock time spent on each frame.
+#[derive(Default)]
+struct TickTimings {
+    started: Option<Instant>,
+    ticks: u32,
+    total: Duration,
+    slowest: Duration,
+}
+
+fn start_game(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::InGame);
+}
+
+fn advance_clock(
+    mut run: ResMut<HeadlessRun>,
+    fixed_time: Res<FixedTime>,
+    mut update_strategy: ResMut<TimeUpdateStrategy>,
+) {
+    run.clock += fixed_time.period;
+    *update_strategy = TimeUpdateStrategy::ManualInstant(run.clock);
+    if let Some(timings) = &mut run.timings {
+        timings.started = Some(Instant::now());
+    }
+}
+
+fn count_ticks(
+    mut run: ResMut<HeadlessRun>,
+    state: Res<State<AppState>>,
+    player_query: Query<&Player>,
+    enemy_spawner_query: Query<&EnemySpawner>,
+    stats: Res<RunStats>,
+    grid: Res<EnemyGrid>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    run.ticks_remaining = run.ticks_remaining.saturating_sub(1);
+    if let Some(timings) = &mut run.timings {
+        if let Some(started) = timings.started.take() {
+            let elapsed = started.elapsed();
+            timings.ticks += 1;
+            timings.total += elapsed;
+            timings.slowest = timings.slowest.max(elapsed);
+        }
+    }
+
+    let run_over = state.0 == AppState::Scoreboard;
+    if run.ticks_remaining > 0 && !run_over {
+        return;
+    }
+
+    if let Ok(enemy_spawner) = enemy_spawner_query.get_single() {
+        let health = player_query.get_single().map_or(0, |player| player.health);
+        info!(
+            "headless run finished: seed {}, stage {}, score {}, health {}",
+            enemy_spawner.seed, enemy_spawner.stage, enemy_spawner.score, health
+        );
+        for line in stats.summary() {
+            info!("{line}");
+        }
+    }
+    if let Some(timings) = &run.timings {
+        log_timings(timings, &grid);
+    }
+    app_exit_events.send(AppExit);
+}
+
+/// Logs the frame times, then times the grid's rebuild and queries on the
+/// last step's enemies against checking every enemy.
+fn log_timings(timings: &TickTimings, grid: &EnemyGrid) {
+    let mean = timings.total / timings.ticks.max(1);
+    info!(
+        "{} ticks: mean {:.2} ms, slowest {:.2} ms",
+        timings.ticks,
+        mean.as_secs_f64() * 1000.0,
+        timings.slowest.as_secs_f64() * 1000.0
+    );
+
+    const RADIUS: f32 = 50.0;
+    const NEAREST: usize = 8;
+    let enemies: Vec<_> = grid.iter().copied().collect();
+    if enemies.is_empty() {
+        return;
+    }
+    let millis = |started: Instant| started.elapsed().as_secs_f64() * 1000.0;
+
+    let started = Instant::now();
+    let mut rebuilt = EnemyGrid::default();
+    rebuilt.rebuild(enemies.iter().copied());
+    let rebuild = millis(started);
+
+    let started = Instant::now();
+    let grid_found: usize = enemies
+        .iter()
+        .map(|enemy| rebuilt.within(enemy.position, RADIUS).count())
+        .sum();
+    let grid_radius = millis(started);
+
+    let started = Instant::now();
+    let scan_found: usize = enemies
+        .iter()
+        .map(|enemy| {
+            enemies
+                .iter()
+                .filter(|other| other.position.distance(enemy.position) < RADIUS)
+                .count()
+        })
+        .sum();
+    let scan_radius = millis(started);
+
+    let started = Instant::now();
+    let nearest: usize = enemies
+        .iter()
+        .map(|enemy| rebuilt.nearest(enemy.position, NEAREST).len())
+        .sum();
+    let grid_nearest = millis(started);
+
+    info!(
+        "enemy grid with {} enemies: rebuild {rebuild:.2} ms, radius {RADIUS} around each {grid_radius:.2} ms ({grid_found} found, scanning every enemy {scan_radius:.2} ms for {scan_found}), nearest {NEAREST} to each {grid_nearest:.2} ms ({nearest} found)",
+        rebuilt.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{Enemy, PlayerInput, WeaponSelect};
+    use crate::replay::Replay;
+    use crate::settings::LaunchSettings;
+    use std::path::Path;
+
+    const TICKS: u32 = 900;
+
+    /// Strafes, aims around in a circle, fires, reloads and switches weapons,
+    /// so the player's spread draws from the RNG alongside the enemies.
+    fn scripted_replay() -> Replay {
+        let frames = (0..TICKS)
+            .map(|frame| {
+                let angle = frame as f32 * 0.05;
+                PlayerInput {
+                    movement: match frame / 120 % 4 {
+                        0 => Vec2::X,
+                        1 => Vec2::Y,
+                        2 => Vec2::NEG_X,
+                        _ => Vec2::NEG_Y,
+                    },
+                    aim: Some(Vec2::new(angle.cos(), angle.sin()) * 200.0),
+                    fire: frame % 20 == 0,
+                    trigger: false,
+                    reload: frame % 300 == 150,
+                    weapon_select: (frame == 450).then_some(WeaponSelect::Next),
+                }
+            })
+            .collect();
+        Replay {
+            seed: 42,
+            stage: 1,
+            tick_rate: 60,
+            frames,
+        }
+    }
+
+    /// Plays the replay headless and describes where it ended, down to the
+    /// bits of every position.
+    fn play(path: &Path) -> String {
+        let mut app = crate::build_app(LaunchSettings {
+            headless: true,
+            ticks: TICKS + 1,
+            replay: Some(path.to_path_buf()),
+            ..default()
+        });
+        for _ in 0..TICKS {
+            app.update();
+        }
+
+        let world = &mut app.world;
+        let mut state = Vec::new();
+        for spawner in world.query::<&EnemySpawner>().iter(world) {
+            state.push(format!("stage {} score {}", spawner.stage, spawner.score));
+        }
+        for (player, transform) in world.query::<(&Player, &Transform)>().iter(world) {
+            let position = transform
+                .translation
+                .truncate()
+                .to_array()
+                .map(f32::to_bits);
+            state.push(format!("player {} at {position:?}", player.health));
+        }
+        for (enemy, transform) in world.query::<(&Enemy, &Transform)>().iter(world) {
+            let position = transform
+                .translation
+                .truncate()
+                .to_array()
+                .map(f32::to_bits);
+            state.push(format!("enemy {} at {position:?}", enemy.health()));
+        }
+        state.extend(world.resource::<RunStats>().summary());
+        state.join("\n")
+    }
+
+    #[test]
+    fn same_seed_and_replay_end_the_same() {
+        let path =
+            std::env::temp_dir().join(format!("ligma-determinism-{}.replay", std::process::id()));
+        scripted_replay().save(&path).unwrap();
+        let first = play(&path);
+        let second = play(&path);
+        std::fs::remove_file(&path).ok();
+
+        assert!(first.contains("enemy"), "no enemies spawned:\n{first}");
+        assert_eq!(first, second);
+    }
+}
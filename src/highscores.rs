@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// How many runs the table keeps.
+pub const MAX_HIGH_SCORES: usize = 10;
+
+const HIGH_SCORES_FILE: &str = "highscores.ron";
+
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load(PathBuf::from(HIGH_SCORES_FILE)));
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HighScore {
+    /// Three letters entered on the scoreboard. Empty for runs saved before
+    /// initials existed.
+    #[serde(default)]
+    pub initials: String,
+    pub score: i32,
+    pub stage: u32,
+    /// Seconds since the Unix epoch when the run ended.
+    pub date: u64,
+    pub seed: u64,
+    /// Game time the run lasted, in seconds.
+    pub duration: u64,
+}
+
+/// The best runs on this machine, best first, kept in `highscores.ron` next
+/// to the game.
+#[derive(Resource)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+    path: PathBuf,
+}
+
+impl HighScores {
+    fn load(path: PathBuf) -> Self {
+        let entries = match fs::read_to_string(&path) {
+            Ok(contents) => ron::from_str(&contents).unwrap_or_else(|err| {
+                warn!("ignoring unreadable {}: {err}", path.display());
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+
+        Self { entries, path }
+    }
+
+    fn save(&self) {
+        let result = ron::ser::to_string_pretty(&self.entries, default())
+            .map_err(|err| err.to_string())
+            .and_then(|contents| fs::write(&self.path, contents).map_err(|err| err.to_string()));
+
+        if let Err(err) = result {
+            warn!("could not save {}: {err}", self.path.display());
+        }
+    }
+
+    /// Would a run with this score and stage make it into the table?
+    pub fn qualifies(&self, score: i32, stage: u32) -> bool {
+        self.entries.len() < MAX_HIGH_SCORES
+            || self
+                .entries
+                .iter()
+                .any(|entry| (score, stage) > (entry.score, entry.stage))
+    }
+
+    /// Adds a run and saves the table, returning where the run placed if it
+    /// made the cut.
+    pub fn submit(&mut self, high_score: HighScore) -> Option<usize> {
+        if !self.qualifies(high_score.score, high_score.stage) {
+            return None;
+        }
+
+        let rank = self
+            .entries
+            .iter()
+            .position(|entry| (high_score.score, high_score.stage) > (entry.score, entry.stage))
+            .unwrap_or(self.entries.len());
+        self.entries.insert(rank, high_score);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        self.save();
+
+        Some(rank)
+    }
+
+    /// Puts initials on the entry at `rank` and saves the table.
+    pub fn set_initials(&mut self, rank: usize, initials: String) {
+        if let Some(entry) = self.entries.get_mut(rank) {
+            entry.initials = initials;
+            self.save();
+        }
+    }
+}
+
+/// Seconds since the Unix epoch, or 0 if the clock is before it.
+pub fn now_unix_secs() -> u64 {
+    instant::SystemTime::now()
+        .duration_since(instant::SystemTime::UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0)
+}
+
+/// Formats seconds since the Unix epoch as a UTC `YYYY-MM-DD` date.
+pub fn format_date(unix_secs: u64) -> String {
+    // Howard Hinnant's days-to-civil conversion.
+    let days = (unix_secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Formats a number of seconds as `m:ss`.
+pub fn format_duration(secs: u64) -> String {
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
@@ -1,11 +1,20 @@
+use crate::gameplay::{Arsenal, Bestiary, DataAsset, DataAssetLoader, DropTables, Stages};
 use crate::states::AppState;
+use bevy::app::AppExit;
+use bevy::asset::LoadState;
 use bevy::prelude::*;
 
 pub struct LoadingPlugin;
 
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(load_assets.in_set(OnUpdate(AppState::Loading)));
+        app.init_resource::<PendingData>();
+        add_data_asset::<Arsenal>(app);
+        add_data_asset::<DropTables>(app);
+        add_data_asset::<Bestiary>(app);
+        add_data_asset::<Stages>(app);
+        app.add_system(load_assets.in_schedule(OnEnter(AppState::Loading)));
+        app.add_system(finish_loading.in_set(OnUpdate(AppState::Loading)));
     }
 }
 
@@ -19,11 +28,7 @@ pub struct AudioHandles {
     pub hit: Handle<AudioSource>,
 }
 
-fn load_assets(
-    mut commands: Commands,
-    mut next_state: ResMut<NextState<AppState>>,
-    asset_server: Res<AssetServer>,
-) {
+fn load_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
     let audio_handles = AudioHandles {
         cock: asset_server.load("sounds/cock.ogg"),
         gunshot: asset_server.load("sounds/gunshot.ogg"),
@@ -33,5 +38,62 @@ fn load_assets(
         hit: asset_server.load("sounds/hit.ogg"),
     };
     commands.insert_resource(audio_handles);
-    next_state.set(AppState::Menu);
+}
+
+/// Gameplay data files still on their way. `AppState::Loading` ends once this
+/// reaches zero.
+#[derive(Resource, Default)]
+struct PendingData(usize);
+
+#[derive(Resource)]
+struct DataHandle<T: DataAsset>(Handle<T>);
+
+fn add_data_asset<T: DataAsset>(app: &mut App) {
+    app.add_asset::<T>();
+    app.add_asset_loader(DataAssetLoader::<T>::default());
+    app.add_system(load_data::<T>.in_schedule(OnEnter(AppState::Loading)));
+    app.add_system(
+        insert_data::<T>
+            .before(finish_loading)
+            .in_set(OnUpdate(AppState::Loading)),
+    );
+}
+
+fn load_data<T: DataAsset>(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut pending_data: ResMut<PendingData>,
+) {
+    commands.insert_resource(DataHandle::<T>(asset_server.load(T::PATH)));
+    pending_data.0 += 1;
+}
+
+/// Copies the asset into a resource once it has loaded.
+fn insert_data<T: DataAsset>(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    data_handle: Option<Res<DataHandle<T>>>,
+    assets: Res<Assets<T>>,
+    mut pending_data: ResMut<PendingData>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    let Some(data_handle) = data_handle else {
+        return;
+    };
+
+    if asset_server.get_load_state(&data_handle.0) == LoadState::Failed {
+        error!("could not load {}", T::PATH);
+        app_exit_events.send(AppExit);
+    } else if let Some(data) = assets.get(&data_handle.0) {
+        info!("loaded {}", T::PATH);
+        commands.insert_resource(data.clone());
+        commands.remove_resource::<DataHandle<T>>();
+        pending_data.0 -= 1;
+    }
+}
+
+fn finish_loading(pending_data: Res<PendingData>, mut next_state: ResMut<NextState<AppState>>) {
+    if pending_data.0 == 0 {
+        next_state.set(AppState::Menu);
+    }
 }
@@ -2,26 +2,47 @@ use bevy::prelude::*;
 use bevy::window::PresentMode;
 use bevy_turborand::prelude::*;
 use cutscene::CutscenePlugin;
-use gameplay::GameplayPlugin;
+use gameplay::{GameplayPlugin, GameplayPresentationPlugin};
+use headless::HeadlessPlugin;
+use highscores::HighScoresPlugin;
 use loading::LoadingPlugin;
 use mainmenu::MainMenuPlugin;
 use pausemenu::PauseMenuPlugin;
+use replay::ReplayPlugin;
 use scoreboard::ScoreboardPlugin;
+use settings::LaunchSettings;
 use states::AppState;
 
 mod constants;
 mod cutscene;
 mod gameplay;
+mod headless;
+mod highscores;
 mod loading;
 mod mainmenu;
 mod pausemenu;
+mod replay;
 mod scoreboard;
+mod settings;
 mod states;
 
 fn main() {
-    App::new()
-        .add_state::<AppState>()
-        .add_plugins(
+    build_app(LaunchSettings::from_args()).run();
+}
+
+/// The whole game for `settings`, ready to run.
+fn build_app(settings: LaunchSettings) -> App {
+    let mut app = App::new();
+    app.add_state::<AppState>()
+        .insert_resource(settings.clone());
+
+    if settings.headless {
+        app.add_plugin(HeadlessPlugin {
+            ticks: settings.ticks,
+            timings: settings.stress > 0,
+        });
+    } else {
+        app.add_plugins(
             DefaultPlugins
                 .set(WindowPlugin {
                     primary_window: Some(Window {
@@ -36,15 +57,22 @@ fn main() {
                 })
                 .set(ImagePlugin::default_nearest()),
         )
-        .add_plugin(RngPlugin::default())
         .add_plugin(MainMenuPlugin)
         .add_plugin(LoadingPlugin)
         .add_plugin(PauseMenuPlugin)
-        .add_plugin(GameplayPlugin)
+        .add_plugin(GameplayPresentationPlugin)
         .add_plugin(CutscenePlugin)
+        .add_plugin(HighScoresPlugin)
         .add_plugin(ScoreboardPlugin)
-        .add_startup_system(setup_camera)
-        .run()
+        .add_startup_system(setup_camera);
+    }
+
+    // A replay's header overrides the seed, stage and tick rate, so it has to
+    // be loaded before the gameplay plugins read them.
+    app.add_plugin(RngPlugin::default())
+        .add_plugin(ReplayPlugin)
+        .add_plugin(GameplayPlugin);
+    app
 }
 
 fn setup_camera(mut commands: Commands) {
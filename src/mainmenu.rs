@@ -1,5 +1,6 @@
 use crate::constants::{HOVERED_BUTTON, NORMAL_BUTTON, PRESSED_BUTTON};
-use crate::gameplay::{Bullet, Enemy, EnemySpawner, Player};
+use crate::gameplay::{Bullet, Enemy, EnemySpawner, Pickup, Player, SpawnTelegraph, Spit};
+use crate::settings::LaunchSettings;
 use crate::states::AppState;
 use bevy::prelude::*;
 
@@ -25,11 +26,23 @@ struct MenuEntities {
 #[derive(Component)]
 struct Map;
 
+/// Flips `LaunchSettings::hold_to_fire`, for builds without a command line.
+#[derive(Component)]
+struct HoldToFireButton;
+
+fn hold_to_fire_label(hold_to_fire: bool) -> &'static str {
+    if hold_to_fire {
+        "Hold to Fire: On"
+    } else {
+        "Hold to Fire: Off"
+    }
+}
+
 #[derive(Component)]
 struct MainMenuBackground;
 
 fn spawn_map(mut commands: Commands, map_query: Query<&Map>, asset_server: Res<AssetServer>) {
-    for _ in map_query.iter() {
+    if !map_query.is_empty() {
         return;
     }
 
@@ -47,7 +60,7 @@ fn spawn_background(
     background_query: Query<&MainMenuBackground>,
     asset_server: Res<AssetServer>,
 ) {
-    for _ in background_query.iter() {
+    if !background_query.is_empty() {
         return;
     }
 
@@ -66,7 +79,11 @@ fn spawn_background(
         ));
 }
 
-fn create_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn create_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<LaunchSettings>,
+) {
     let start_button = commands
         .spawn(NodeBundle {
             style: Style {
@@ -103,6 +120,29 @@ fn create_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                         },
                     ));
                 });
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(340.0), Val::Px(50.0)),
+                        margin: UiRect::all(Val::Px(10.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .insert(HoldToFireButton)
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        hold_to_fire_label(settings.hold_to_fire),
+                        TextStyle {
+                            font: asset_server.load("fonts/pixelsplitter.ttf"),
+                            font_size: 30.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                    ));
+                });
             parent.spawn(TextBundle::from_section(
                 "WASD to Move.",
                 TextStyle {
@@ -132,15 +172,32 @@ fn create_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.insert_resource(MenuEntities { start_button });
 }
 
+#[allow(clippy::type_complexity)]
 fn process_menu(
     mut next_state: ResMut<NextState<AppState>>,
+    mut settings: ResMut<LaunchSettings>,
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            &Children,
+            Option<&HoldToFireButton>,
+        ),
         (Changed<Interaction>, With<Button>),
     >,
+    mut text_query: Query<&mut Text>,
 ) {
-    for (interaction, mut color) in &mut interaction_query {
+    for (interaction, mut color, children, hold_to_fire_button) in &mut interaction_query {
         match *interaction {
+            Interaction::Clicked if hold_to_fire_button.is_some() => {
+                *color = PRESSED_BUTTON.into();
+                settings.hold_to_fire = !settings.hold_to_fire;
+                for &child in children.iter() {
+                    if let Ok(mut text) = text_query.get_mut(child) {
+                        text.sections[0].value = hold_to_fire_label(settings.hold_to_fire).into();
+                    }
+                }
+            }
             Interaction::Clicked => {
                 *color = PRESSED_BUTTON.into();
                 next_state.set(AppState::Cutscene);
@@ -166,18 +223,21 @@ fn destroy_menu(
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn destroy_world_things(
     mut commands: Commands,
     player_query: Query<(&Player, Entity)>,
-    enemy_query: Query<(&Enemy, Entity)>,
+    enemy_query: Query<Entity, Or<(With<Enemy>, With<SpawnTelegraph>)>>,
     enemy_spawner_query: Query<(&EnemySpawner, Entity)>,
     bullet_query: Query<(&Bullet, Entity)>,
+    pickup_query: Query<(&Pickup, Entity)>,
+    spit_query: Query<Entity, With<Spit>>,
 ) {
     for (_, entity) in player_query.iter() {
         commands.entity(entity).despawn_recursive();
     }
 
-    for (_, entity) in enemy_query.iter() {
+    for entity in enemy_query.iter() {
         commands.entity(entity).despawn_recursive();
     }
 
@@ -188,4 +248,12 @@ fn destroy_world_things(
     for (_, entity) in bullet_query.iter() {
         commands.entity(entity).despawn_recursive();
     }
+
+    for (_, entity) in pickup_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for entity in spit_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
 }
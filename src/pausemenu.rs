@@ -82,6 +82,7 @@ fn create_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.insert_resource(MenuEntities { pause_menu });
 }
 
+#[allow(clippy::type_complexity)]
 fn process_menu(
     mut next_state: ResMut<NextState<AppState>>,
     mut interaction_query: Query<
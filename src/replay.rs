@@ -0,0 +1,302 @@
+use crate::gameplay::{EnemySpawner, FixedSet, PlayerInput, WeaponSelect};
+use crate::settings::LaunchSettings;
+use crate::states::AppState;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Records the inputs of a run to the `--record` file, or plays back the
+/// `--replay` file in place of the keyboard and mouse.
+///
+/// A replay stores the seed, the starting stage, the tick rate, and for every
+/// fixed update spent in `AppState::InGame` the `PlayerInput` the simulation
+/// consumed. Playback feeds those back one fixed update at a time, so the run
+/// plays out the same whatever the frame rate.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let mut settings = app.world.resource_mut::<LaunchSettings>();
+
+        if let Some(path) = settings.replay.clone() {
+            match Replay::load(&path) {
+                Ok(replay) => {
+                    info!(
+                        "replaying {} frames from {}",
+                        replay.frames.len(),
+                        path.display()
+                    );
+                    settings.seed = Some(replay.seed);
+                    settings.stage = replay.stage;
+                    settings.tick_rate = replay.tick_rate;
+                    app.insert_resource(ReplayPlayback {
+                        frames: replay.frames.into(),
+                    })
+                    .add_system(
+                        play_frame
+                            .in_schedule(CoreSchedule::FixedUpdate)
+                            .in_base_set(FixedSet::Input)
+                            .run_if(in_state(AppState::InGame)),
+                    );
+                }
+                Err(err) => error!("could not load replay {}: {err}", path.display()),
+            }
+        }
+
+        let settings = app.world.resource::<LaunchSettings>();
+        if let Some(path) = settings.record.clone() {
+            app.insert_resource(ReplayRecorder { path, replay: None })
+                .add_system(
+                    start_recording
+                        .in_schedule(CoreSchedule::FixedUpdate)
+                        .in_base_set(FixedSet::Input),
+                )
+                .add_system(
+                    record_frame
+                        .after(start_recording)
+                        .after(play_frame)
+                        .in_schedule(CoreSchedule::FixedUpdate)
+                        .in_base_set(FixedSet::Input)
+                        .run_if(in_state(AppState::InGame)),
+                )
+                .add_system(save_recording.in_schedule(OnEnter(AppState::Scoreboard)))
+                .add_system(save_recording.in_schedule(OnEnter(AppState::Menu)))
+                .add_system(save_recording_on_exit.in_base_set(CoreSet::Last));
+        }
+    }
+}
+
+const MAGIC: &[u8; 4] = b"LGRP";
+const VERSION: u8 = 4;
+/// Most frames reserved before reading them, ten minutes at 60 Hz.
+const MAX_PREALLOCATED_FRAMES: u32 = 36_000;
+
+const FIRE: u16 = 1 << 0;
+const RELOAD: u16 = 1 << 1;
+const AIM: u16 = 1 << 2;
+const MOVE_X_SHIFT: u16 = 3;
+const MOVE_Y_SHIFT: u16 = 5;
+const WEAPON: u16 = 1 << 7;
+const TRIGGER: u16 = 1 << 8;
+
+const WEAPON_NEXT: u8 = u8::MAX;
+const WEAPON_PREVIOUS: u8 = u8::MAX - 1;
+
+pub struct Replay {
+    pub seed: u64,
+    pub stage: u32,
+    pub tick_rate: u32,
+    /// The input of each fixed update.
+    pub frames: Vec<PlayerInput>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::read(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        writer.write_all(&self.seed.to_le_bytes())?;
+        writer.write_all(&self.stage.to_le_bytes())?;
+        writer.write_all(&self.tick_rate.to_le_bytes())?;
+        writer.write_all(&(self.frames.len() as u32).to_le_bytes())?;
+
+        for input in &self.frames {
+            let mut flags = encode_axis(input.movement.x) << MOVE_X_SHIFT
+                | encode_axis(input.movement.y) << MOVE_Y_SHIFT;
+            if input.fire {
+                flags |= FIRE;
+            }
+            if input.reload {
+                flags |= RELOAD;
+            }
+            if input.aim.is_some() {
+                flags |= AIM;
+            }
+            if input.weapon_select.is_some() {
+                flags |= WEAPON;
+            }
+            if input.trigger {
+                flags |= TRIGGER;
+            }
+
+            writer.write_all(&flags.to_le_bytes())?;
+            if let Some(aim) = input.aim {
+                writer.write_all(&aim.x.to_le_bytes())?;
+                writer.write_all(&aim.y.to_le_bytes())?;
+            }
+            if let Some(weapon_select) = input.weapon_select {
+                let byte = match weapon_select {
+                    WeaponSelect::Slot(slot) => slot.min(WEAPON_PREVIOUS as usize - 1) as u8,
+                    WeaponSelect::Next => WEAPON_NEXT,
+                    WeaponSelect::Previous => WEAPON_PREVIOUS,
+                };
+                writer.write_all(&[byte])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read(reader: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_array::<1>(reader)?[0] != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a replay file, or from another version",
+            ));
+        }
+
+        let seed = u64::from_le_bytes(read_array(reader)?);
+        let stage = u32::from_le_bytes(read_array(reader)?);
+        let tick_rate = u32::from_le_bytes(read_array(reader)?);
+        let frame_count = u32::from_le_bytes(read_array(reader)?);
+        if tick_rate == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "a replay's tick rate has to be above 0",
+            ));
+        }
+
+        // The count comes from the file, so a corrupt one mustn't decide how
+        // much to allocate up front. A short file fails on the reads instead.
+        let mut frames = Vec::with_capacity(frame_count.min(MAX_PREALLOCATED_FRAMES) as usize);
+        for _ in 0..frame_count {
+            let flags = u16::from_le_bytes(read_array(reader)?);
+            let aim = if flags & AIM != 0 {
+                let x = f32::from_le_bytes(read_array(reader)?);
+                let y = f32::from_le_bytes(read_array(reader)?);
+                Some(Vec2::new(x, y))
+            } else {
+                None
+            };
+            let weapon_select = if flags & WEAPON != 0 {
+                Some(match read_array::<1>(reader)?[0] {
+                    WEAPON_NEXT => WeaponSelect::Next,
+                    WEAPON_PREVIOUS => WeaponSelect::Previous,
+                    slot => WeaponSelect::Slot(slot as usize),
+                })
+            } else {
+                None
+            };
+
+            frames.push(PlayerInput {
+                movement: Vec2::new(
+                    decode_axis(flags >> MOVE_X_SHIFT),
+                    decode_axis(flags >> MOVE_Y_SHIFT),
+                ),
+                aim,
+                fire: flags & FIRE != 0,
+                trigger: flags & TRIGGER != 0,
+                reload: flags & RELOAD != 0,
+                weapon_select,
+            });
+        }
+
+        Ok(Self {
+            seed,
+            stage,
+            tick_rate,
+            frames,
+        })
+    }
+}
+
+fn encode_axis(value: f32) -> u16 {
+    if value > 0.0 {
+        1
+    } else if value < 0.0 {
+        2
+    } else {
+        0
+    }
+}
+
+fn decode_axis(bits: u16) -> f32 {
+    match bits & 0b11 {
+        1 => 1.0,
+        2 => -1.0,
+        _ => 0.0,
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+#[derive(Resource)]
+struct ReplayRecorder {
+    path: PathBuf,
+    replay: Option<Replay>,
+}
+
+#[derive(Resource)]
+struct ReplayPlayback {
+    frames: VecDeque<PlayerInput>,
+}
+
+fn start_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    enemy_spawner_query: Query<&EnemySpawner, Added<EnemySpawner>>,
+    settings: Res<LaunchSettings>,
+) {
+    if let Ok(enemy_spawner) = enemy_spawner_query.get_single() {
+        recorder.replay = Some(Replay {
+            seed: enemy_spawner.seed,
+            stage: enemy_spawner.stage,
+            tick_rate: settings.tick_rate,
+            frames: Vec::new(),
+        });
+    }
+}
+
+fn record_frame(mut recorder: ResMut<ReplayRecorder>, player_input: Res<PlayerInput>) {
+    if let Some(replay) = recorder.replay.as_mut() {
+        replay.frames.push(player_input.clone());
+    }
+}
+
+fn save_recording(mut recorder: ResMut<ReplayRecorder>) {
+    if let Some(replay) = recorder.replay.take() {
+        match replay.save(&recorder.path) {
+            Ok(()) => info!(
+                "recorded {} frames to {}",
+                replay.frames.len(),
+                recorder.path.display()
+            ),
+            Err(err) => error!(
+                "could not save replay to {}: {err}",
+                recorder.path.display()
+            ),
+        }
+    }
+}
+
+fn save_recording_on_exit(recorder: ResMut<ReplayRecorder>, app_exit_events: EventReader<AppExit>) {
+    if !app_exit_events.is_empty() {
+        save_recording(recorder);
+    }
+}
+
+fn play_frame(mut playback: ResMut<ReplayPlayback>, mut player_input: ResMut<PlayerInput>) {
+    if let Some(input) = playback.frames.pop_front() {
+        *player_input = input;
+        if playback.frames.is_empty() {
+            info!("replay finished");
+        }
+    }
+}
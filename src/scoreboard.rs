@@ -1,4 +1,6 @@
 use crate::constants::{HOVERED_BUTTON, NORMAL_BUTTON, PRESSED_BUTTON};
+use crate::gameplay::{EnemySpawner, GameClock, RunStats};
+use crate::highscores::{format_date, format_duration, now_unix_secs, HighScore, HighScores};
 use crate::states::AppState;
 use bevy::prelude::*;
 
@@ -8,6 +10,13 @@ impl Plugin for ScoreboardPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(create_menu.in_schedule(OnEnter(AppState::Scoreboard)));
         app.add_system(process_menu.in_set(OnUpdate(AppState::Scoreboard)));
+        app.add_system(type_initials.in_set(OnUpdate(AppState::Scoreboard)));
+        app.add_system(
+            update_initials
+                .after(process_menu)
+                .after(type_initials)
+                .in_set(OnUpdate(AppState::Scoreboard)),
+        );
         app.add_system(destroy_menu.in_schedule(OnExit(AppState::Scoreboard)));
     }
 }
@@ -20,7 +29,78 @@ struct ScoreBoardEntities {
 #[derive(Component)]
 struct ScoreBoardSprite;
 
-fn create_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+const INITIALS_LENGTH: usize = 3;
+
+/// Arcade-style initials for a run that made the high-score table. The run is
+/// already in the table as "AAA"; the letters are written over it when the
+/// player leaves the scoreboard.
+#[derive(Resource)]
+struct InitialsEntry {
+    rank: usize,
+    letters: [u8; INITIALS_LENGTH],
+    cursor: usize,
+}
+
+impl InitialsEntry {
+    fn step_letter(&mut self, amount: i8) {
+        let letter = &mut self.letters[self.cursor];
+        *letter = b'A' + (*letter as i8 - b'A' as i8 + amount).rem_euclid(26) as u8;
+    }
+
+    fn type_letter(&mut self, letter: char) {
+        self.letters[self.cursor] = letter.to_ascii_uppercase() as u8;
+        self.cursor = (self.cursor + 1).min(INITIALS_LENGTH - 1);
+    }
+
+    fn initials(&self) -> String {
+        String::from_utf8_lossy(&self.letters).into_owned()
+    }
+}
+
+/// Selects which letter is being entered.
+#[derive(Component)]
+struct InitialSlot(usize);
+
+/// Cycles a letter forward or back through the alphabet.
+#[derive(Component)]
+struct InitialStep {
+    slot: usize,
+    amount: i8,
+}
+
+#[derive(Component)]
+struct InitialText(usize);
+
+/// The table row of the run that just ended.
+#[derive(Component)]
+struct RunRow;
+
+fn create_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    enemy_spawner_query: Query<&EnemySpawner>,
+    clock: Res<GameClock>,
+    mut high_scores: ResMut<HighScores>,
+    stats: Res<RunStats>,
+) {
+    let mut run_summary = String::new();
+    let mut run_rank = None;
+
+    if let Ok(enemy_spawner) = enemy_spawner_query.get_single() {
+        run_summary = format!(
+            "Score {} - Stage {} - Seed {}",
+            enemy_spawner.score, enemy_spawner.stage, enemy_spawner.seed
+        );
+        run_rank = high_scores.submit(HighScore {
+            initials: "AAA".to_string(),
+            score: enemy_spawner.score,
+            stage: enemy_spawner.stage,
+            date: now_unix_secs(),
+            seed: enemy_spawner.seed,
+            duration: (clock.elapsed() - enemy_spawner.run_start).as_secs(),
+        });
+    }
+
     let score_menu = commands
         .spawn(NodeBundle {
             style: Style {
@@ -78,6 +158,54 @@ fn create_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                             color: Color::rgb(0.0, 0.0, 0.0),
                         },
                     ));
+                    parent.spawn(TextBundle::from_section(
+                        run_summary,
+                        TextStyle {
+                            font: asset_server.load("fonts/pixelsplitter.ttf"),
+                            font_size: 28.0,
+                            color: Color::rgb(0.0, 0.0, 0.0),
+                        },
+                    ));
+                    for line in stats.summary() {
+                        parent.spawn(TextBundle::from_section(
+                            line,
+                            TextStyle {
+                                font: asset_server.load("fonts/pixelsplitter.ttf"),
+                                font_size: 18.0,
+                                color: Color::rgb(0.0, 0.0, 0.0),
+                            },
+                        ));
+                    }
+                    if run_rank.is_some() {
+                        spawn_initials_entry(parent, &asset_server);
+                    }
+                    parent.spawn(TextBundle::from_section(
+                        "High Scores",
+                        TextStyle {
+                            font: asset_server.load("fonts/pixelsplitter.ttf"),
+                            font_size: 28.0,
+                            color: Color::rgb(0.0, 0.0, 0.0),
+                        },
+                    ));
+                    for (rank, entry) in high_scores.entries.iter().enumerate() {
+                        // The run that just ended stands out when it placed.
+                        let color = if run_rank == Some(rank) {
+                            Color::RED
+                        } else {
+                            Color::rgb(0.0, 0.0, 0.0)
+                        };
+                        let mut row = parent.spawn(TextBundle::from_section(
+                            high_score_row(rank, entry),
+                            TextStyle {
+                                font: asset_server.load("fonts/pixelsplitter.ttf"),
+                                font_size: 18.0,
+                                color,
+                            },
+                        ));
+                        if run_rank == Some(rank) {
+                            row.insert(RunRow);
+                        }
+                    }
                 });
         })
         .id();
@@ -100,20 +228,158 @@ fn create_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
         })
         .insert(ScoreBoardSprite);
     commands.insert_resource(ScoreBoardEntities { score_menu });
+    if let Some(rank) = run_rank {
+        commands.insert_resource(InitialsEntry {
+            rank,
+            letters: [b'A'; INITIALS_LENGTH],
+            cursor: 0,
+        });
+    }
 }
 
+fn spawn_initials_entry(parent: &mut ChildBuilder, asset_server: &AssetServer) {
+    let text_style = |font_size, color| TextStyle {
+        font: asset_server.load("fonts/pixelsplitter.ttf"),
+        font_size,
+        color,
+    };
+
+    parent.spawn(TextBundle::from_section(
+        "New high score! Enter your initials",
+        text_style(28.0, Color::RED),
+    ));
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for slot in 0..INITIALS_LENGTH {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            margin: UiRect::horizontal(Val::Px(8.0)),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        let button_style = Style {
+                            size: Size::new(Val::Px(50.0), Val::Px(50.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        };
+
+                        parent
+                            .spawn(ButtonBundle {
+                                style: button_style.clone(),
+                                ..default()
+                            })
+                            .insert(InitialStep { slot, amount: 1 })
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    "+",
+                                    text_style(32.0, Color::rgb(0.9, 0.9, 0.9)),
+                                ));
+                            });
+                        parent
+                            .spawn(ButtonBundle {
+                                style: button_style.clone(),
+                                background_color: Color::NONE.into(),
+                                ..default()
+                            })
+                            .insert(InitialSlot(slot))
+                            .with_children(|parent| {
+                                parent
+                                    .spawn(TextBundle::from_section(
+                                        "A",
+                                        text_style(48.0, Color::rgb(0.0, 0.0, 0.0)),
+                                    ))
+                                    .insert(InitialText(slot));
+                            });
+                        parent
+                            .spawn(ButtonBundle {
+                                style: button_style,
+                                ..default()
+                            })
+                            .insert(InitialStep { slot, amount: -1 })
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    "-",
+                                    text_style(32.0, Color::rgb(0.9, 0.9, 0.9)),
+                                ));
+                            });
+                    });
+            }
+        });
+}
+
+fn high_score_row(rank: usize, entry: &HighScore) -> String {
+    let initials = if entry.initials.is_empty() {
+        "---"
+    } else {
+        &entry.initials
+    };
+
+    format!(
+        "{}. {}  {}  Stage {}  {}  {}  Seed {}",
+        rank + 1,
+        initials,
+        entry.score,
+        entry.stage,
+        format_date(entry.date),
+        format_duration(entry.duration),
+        entry.seed
+    )
+}
+
+#[allow(clippy::type_complexity)]
 fn process_menu(
     mut next_state: ResMut<NextState<AppState>>,
+    mut initials_entry: Option<ResMut<InitialsEntry>>,
+    mut high_scores: ResMut<HighScores>,
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            Option<&InitialSlot>,
+            Option<&InitialStep>,
+        ),
         (Changed<Interaction>, With<Button>),
     >,
 ) {
-    for (interaction, mut color) in &mut interaction_query {
+    for (interaction, mut color, slot, step) in &mut interaction_query {
+        // The letters themselves are plain text, not buttons to be colored.
+        if let Some(slot) = slot {
+            if let (Interaction::Clicked, Some(entry)) = (interaction, initials_entry.as_mut()) {
+                entry.cursor = slot.0;
+            }
+            continue;
+        }
+
         match *interaction {
             Interaction::Clicked => {
                 *color = PRESSED_BUTTON.into();
-                next_state.set(AppState::Menu);
+                match (step, initials_entry.as_mut()) {
+                    (Some(step), Some(entry)) => {
+                        entry.cursor = step.slot;
+                        entry.step_letter(step.amount);
+                    }
+                    (Some(_), None) => {}
+                    (None, entry) => {
+                        if let Some(entry) = entry {
+                            high_scores.set_initials(entry.rank, entry.initials());
+                        }
+                        next_state.set(AppState::Menu);
+                    }
+                }
             }
             Interaction::Hovered => {
                 *color = HOVERED_BUTTON.into();
@@ -125,12 +391,86 @@ fn process_menu(
     }
 }
 
+/// Letters type straight into the current slot, the arrow keys move between
+/// slots and cycle letters, and Enter saves the initials and leaves.
+fn type_initials(
+    mut next_state: ResMut<NextState<AppState>>,
+    initials_entry: Option<ResMut<InitialsEntry>>,
+    mut high_scores: ResMut<HighScores>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut received_characters: EventReader<ReceivedCharacter>,
+) {
+    let Some(mut entry) = initials_entry else {
+        received_characters.clear();
+        return;
+    };
+
+    for received in received_characters.iter() {
+        if received.char.is_ascii_alphabetic() {
+            entry.type_letter(received.char);
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        entry.step_letter(1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        entry.step_letter(-1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Left) || keyboard_input.just_pressed(KeyCode::Back) {
+        entry.cursor = entry.cursor.saturating_sub(1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Right) {
+        entry.cursor = (entry.cursor + 1).min(INITIALS_LENGTH - 1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        high_scores.set_initials(entry.rank, entry.initials());
+        next_state.set(AppState::Menu);
+    }
+}
+
+fn update_initials(
+    initials_entry: Option<Res<InitialsEntry>>,
+    high_scores: Res<HighScores>,
+    mut initial_text_query: Query<(&InitialText, &mut Text), Without<RunRow>>,
+    mut run_row_query: Query<&mut Text, With<RunRow>>,
+) {
+    let Some(entry) = initials_entry else {
+        return;
+    };
+    if !entry.is_changed() {
+        return;
+    }
+
+    for (initial_text, mut text) in initial_text_query.iter_mut() {
+        let section = &mut text.sections[0];
+        section.value = char::from(entry.letters[initial_text.0]).to_string();
+        section.style.color = if initial_text.0 == entry.cursor {
+            Color::RED
+        } else {
+            Color::rgb(0.0, 0.0, 0.0)
+        };
+    }
+
+    if let (Ok(mut text), Some(run)) = (
+        run_row_query.get_single_mut(),
+        high_scores.entries.get(entry.rank),
+    ) {
+        let run = HighScore {
+            initials: entry.initials(),
+            ..run.clone()
+        };
+        text.sections[0].value = high_score_row(entry.rank, &run);
+    }
+}
+
 fn destroy_menu(
     mut commands: Commands,
     menu_data: Res<ScoreBoardEntities>,
     scoreboard_sprite_query: Query<(&ScoreBoardSprite, Entity)>,
 ) {
     commands.entity(menu_data.score_menu).despawn_recursive();
+    commands.remove_resource::<InitialsEntry>();
     for (_, entity) in scoreboard_sprite_query.iter() {
         commands.entity(entity).despawn_recursive();
     }
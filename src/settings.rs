@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+/// Options picked at launch from the command line.
+///
+/// `--headless` runs the gameplay loop without a window, renderer or audio.
+/// `--ticks <n>` sets how many frames a headless run lasts.
+/// `--seed <n>` (or the `LIGMA_SEED` environment variable) makes every run use
+/// the same RNG seed, and `--stage <n>` starts runs at a later stage, so a run
+/// shown on the scoreboard can be replayed.
+/// `--tick-rate <hz>` sets how many times per second the simulation steps.
+/// `--time-scale <x>` runs gameplay faster or slower than real time.
+/// `--record <file>` saves the inputs of each run, and `--replay <file>` plays
+/// a saved run back.
+/// `--hold-to-fire` keeps firing for as long as the mouse button is held; the
+/// main menu toggles it too.
+/// `--stress <n>` drops `n` extra enemies into the arena when a run starts, and
+/// headless runs then log how long each tick and the enemy grid took.
+#[derive(Resource, Clone, Debug)]
+pub struct LaunchSettings {
+    pub headless: bool,
+    pub ticks: u32,
+    pub seed: Option<u64>,
+    pub stage: u32,
+    pub tick_rate: u32,
+    pub time_scale: f32,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub hold_to_fire: bool,
+    pub stress: u32,
+}
+
+impl Default for LaunchSettings {
+    fn default() -> Self {
+        Self {
+            headless: false,
+            ticks: 10_000,
+            seed: None,
+            stage: 1,
+            tick_rate: 60,
+            time_scale: 1.0,
+            record: None,
+            replay: None,
+            hold_to_fire: false,
+            stress: 0,
+        }
+    }
+}
+
+impl LaunchSettings {
+    pub fn from_args() -> Self {
+        let mut settings = Self::default();
+
+        if let Ok(seed) = std::env::var("LIGMA_SEED") {
+            match seed.parse() {
+                Ok(seed) => settings.seed = Some(seed),
+                Err(_) => eprintln!("LIGMA_SEED expects a number"),
+            }
+        }
+
+        let mut args = std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => settings.headless = true,
+                "--ticks" => match args.next().map(|value| value.parse()) {
+                    Some(Ok(ticks)) => settings.ticks = ticks,
+                    _ => eprintln!("--ticks expects a number of frames"),
+                },
+                "--seed" => match args.next().map(|value| value.parse()) {
+                    Some(Ok(seed)) => settings.seed = Some(seed),
+                    _ => eprintln!("--seed expects a number"),
+                },
+                "--stage" => match args.next().map(|value| value.parse()) {
+                    Some(Ok(stage)) if stage > 0 => settings.stage = stage,
+                    _ => eprintln!("--stage expects a stage number starting at 1"),
+                },
+                "--tick-rate" => match args.next().map(|value| value.parse()) {
+                    Some(Ok(hz)) if hz > 0 => settings.tick_rate = hz,
+                    _ => eprintln!("--tick-rate expects a positive number of steps per second"),
+                },
+                "--time-scale" => match args.next().map(|value| value.parse()) {
+                    Some(Ok(scale)) if scale > 0.0 => settings.time_scale = scale,
+                    _ => eprintln!("--time-scale expects a positive number"),
+                },
+                "--record" => match args.next() {
+                    Some(path) => settings.record = Some(path.into()),
+                    None => eprintln!("--record expects a file path"),
+                },
+                "--replay" => match args.next() {
+                    Some(path) => settings.replay = Some(path.into()),
+                    None => eprintln!("--replay expects a file path"),
+                },
+                "--hold-to-fire" => settings.hold_to_fire = true,
+                "--stress" => match args.next().map(|value| value.parse()) {
+                    Some(Ok(count)) => settings.stress = count,
+                    _ => eprintln!("--stress expects a number of enemies"),
+                },
+                _ => eprintln!("ignoring unknown argument {arg}"),
+            }
+        }
+
+        settings
+    }
+}